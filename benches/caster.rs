@@ -0,0 +1,63 @@
+// A full fan of rays across the view. Rays step along until they hit
+// something, so long sight lines cost the most.
+mod common;
+
+use common::{BLOCK_SIZE, POSES, load, stand_at};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use raycaster::{Assets, Player, World, cast_ray, load_maze};
+use std::f32::consts::PI;
+use std::hint::black_box;
+
+const RAYS: usize = 320;
+
+fn cast_fan(world: &World, player: &Player) -> f32 {
+    let fov_start = player.a - player.fov / 2.0;
+    let fov_step = player.fov / RAYS as f32;
+    (0..RAYS)
+        .map(|i| {
+            let a = fov_start + i as f32 * fov_step;
+            cast_ray(&world.maze, player, a, BLOCK_SIZE).distance
+        })
+        .sum()
+}
+
+fn open_room_and_corridor(c: &mut Criterion) {
+    // The middle of a wide room, and one end of a corridor looking along it
+    let assets = Assets::new();
+    let mut room = World::new(
+        load_maze("benches/open_arena.txt", &[]).unwrap(),
+        BLOCK_SIZE,
+        &assets,
+    );
+    room.player.pos.x = 32.0 * BLOCK_SIZE as f32;
+    room.player.pos.y = 16.0 * BLOCK_SIZE as f32;
+    room.player.a = PI / 6.0;
+    let corridor_level = load_maze("benches/long_corridor.txt", &[]).unwrap();
+    let mut corridor = World::new(corridor_level, BLOCK_SIZE, &assets);
+    corridor.player.a = 0.0;
+
+    let mut group = c.benchmark_group("cast_ray");
+    for (name, world) in [("open_room", &room), ("long_corridor", &corridor)] {
+        group.bench_function(name, |b| {
+            b.iter(|| cast_fan(world, black_box(&world.player)))
+        });
+    }
+    group.finish();
+}
+
+fn shipped_levels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("cast_ray_levels");
+    for (level, poses) in POSES {
+        let mut world = load(level);
+        for pose in poses {
+            stand_at(&mut world, pose);
+            group.bench_function(BenchmarkId::new(level, pose.name), |b| {
+                b.iter(|| cast_fan(&world, black_box(&world.player)))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, open_room_and_corridor, shipped_levels);
+criterion_main!(benches);
@@ -0,0 +1,95 @@
+// Fixed places to stand in each shipped level, so every run of the
+// benchmarks looks at exactly the same scenes
+use raycaster::{Assets, World, load_enemy_types, load_maze};
+use std::f32::consts::PI;
+
+pub const BLOCK_SIZE: usize = 100;
+
+pub struct Pose {
+    pub name: &'static str,
+    pub cell: (usize, usize), // Column and row of the maze grid
+    pub angle: f32,
+}
+
+pub const POSES: [(&str, &[Pose]); 3] = [
+    (
+        "level1.txt",
+        &[
+            // Down the long outdoor corridor over the platforms
+            Pose {
+                name: "start",
+                cell: (1, 1),
+                angle: 0.0,
+            },
+            Pose {
+                name: "maze",
+                cell: (10, 13),
+                angle: PI,
+            },
+            Pose {
+                name: "checkpoint",
+                cell: (1, 15),
+                angle: 0.0,
+            },
+        ],
+    ),
+    (
+        "level2.txt",
+        &[
+            Pose {
+                name: "start",
+                cell: (1, 1),
+                angle: PI / 2.0,
+            },
+            // Past the diagonal walls toward the chaser
+            Pose {
+                name: "diagonals",
+                cell: (13, 7),
+                angle: 0.0,
+            },
+            Pose {
+                name: "low_ceiling",
+                cell: (10, 15),
+                angle: 0.0,
+            },
+        ],
+    ),
+    (
+        "level3.txt",
+        &[
+            // Standing in a portal, looking down the west side
+            Pose {
+                name: "start",
+                cell: (1, 1),
+                angle: PI / 2.0,
+            },
+            Pose {
+                name: "mirror",
+                cell: (10, 13),
+                angle: PI / 2.0,
+            },
+            Pose {
+                name: "glass",
+                cell: (4, 11),
+                angle: -PI / 2.0,
+            },
+        ],
+    ),
+];
+
+pub fn load(level: &str) -> World {
+    let enemy_types = load_enemy_types("enemies.txt").unwrap();
+    World::new(
+        load_maze(level, &enemy_types).unwrap(),
+        BLOCK_SIZE,
+        &Assets::new(),
+    )
+}
+
+// Put the player in the middle of the pose's cell, facing its way
+pub fn stand_at(world: &mut World, pose: &Pose) {
+    let (col, row) = pose.cell;
+    world.player.pos.x = (col * BLOCK_SIZE + BLOCK_SIZE / 2) as f32;
+    world.player.pos.y = (row * BLOCK_SIZE + BLOCK_SIZE / 2) as f32;
+    world.player.a = pose.angle;
+}
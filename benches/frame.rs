@@ -0,0 +1,43 @@
+// Whole frames of each shipped level, 3D view, sprites and HUD, drawn into
+// memory from fixed poses
+mod common;
+
+use common::{POSES, load, stand_at};
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use raycaster::framebuffer::DEFAULT_RAYS;
+use raycaster::playing::render_world;
+use raycaster::{Assets, Scaling, SoftwareCanvas, Viewport, WallTextures, load_enemy_types};
+
+const WIDTH: i32 = 640;
+const HEIGHT: i32 = 480;
+
+fn frames(c: &mut Criterion) {
+    let wall_textures =
+        WallTextures::new(&load_enemy_types("enemies.txt").unwrap(), &Assets::new());
+    let mut viewport = Viewport::new(DEFAULT_RAYS, 1.0, Scaling::Pixel);
+    let mut canvas = SoftwareCanvas::new(WIDTH, HEIGHT);
+
+    let mut group = c.benchmark_group("frame");
+    for (level, poses) in POSES {
+        let mut world = load(level);
+        for pose in poses {
+            stand_at(&mut world, pose);
+            group.bench_function(BenchmarkId::new(level, pose.name), |b| {
+                b.iter(|| {
+                    render_world(
+                        &mut canvas,
+                        &world,
+                        &wall_textures,
+                        &mut viewport,
+                        WIDTH,
+                        HEIGHT,
+                    )
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, frames);
+criterion_main!(benches);
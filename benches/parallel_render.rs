@@ -0,0 +1,45 @@
+// Frames of a large, open level, where every ray travels a long way and
+// lights the whole floor, drawn with one core and with all of them
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use raycaster::{Assets, SoftwareCanvas, View, WallTextures, World, load_maze, render3d};
+use rayon::ThreadPoolBuilder;
+use std::f32::consts::PI;
+
+const WIDTH: i32 = 1300;
+const HEIGHT: i32 = 900;
+
+fn open_level(c: &mut Criterion) {
+    let assets = Assets::new();
+    let mut world = World::new(
+        load_maze("benches/open_arena.txt", &[]).unwrap(),
+        100,
+        &assets,
+    );
+    world.player.a = PI / 6.0; // Looking down the long side of the arena
+    let wall_textures = WallTextures::new(&[], &assets);
+    let mut canvas = SoftwareCanvas::new(WIDTH, HEIGHT);
+
+    let mut group = c.benchmark_group("open_arena");
+    group.sample_size(20);
+    let mut thread_counts = vec![1, rayon::current_num_threads()];
+    thread_counts.dedup();
+    for rays in [320, WIDTH as usize] {
+        let mut view = View::new(rays);
+        view.width = WIDTH;
+        view.height = HEIGHT;
+        for &threads in &thread_counts {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .unwrap();
+            let id = BenchmarkId::new(format!("{} rays", rays), format!("{} threads", threads));
+            group.bench_function(id, |b| {
+                b.iter(|| pool.install(|| render3d(&mut canvas, &world, &wall_textures, &mut view)))
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, open_level);
+criterion_main!(benches);
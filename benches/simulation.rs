@@ -0,0 +1,64 @@
+// Everything that happens without drawing: loading levels and moving the
+// enemies around
+mod common;
+
+use common::{BLOCK_SIZE, POSES, load, stand_at};
+use criterion::{BatchSize, BenchmarkId, Criterion, criterion_group, criterion_main};
+use raycaster::projectile::ProjectilePool;
+use raycaster::{Assets, World, load_enemy_types, load_maze};
+use std::hint::black_box;
+
+const FRAME: f32 = 1.0 / 60.0;
+
+fn level_loading(c: &mut Criterion) {
+    let enemy_types = load_enemy_types("enemies.txt").unwrap();
+    let assets = Assets::new();
+    let mut group = c.benchmark_group("load_level");
+    for (level, _) in POSES {
+        group.bench_function(level, |b| {
+            b.iter(|| {
+                World::new(
+                    load_maze(black_box(level), &enemy_types).unwrap(),
+                    BLOCK_SIZE,
+                    &assets,
+                )
+            })
+        });
+    }
+    group.finish();
+}
+
+fn enemy_ticks(c: &mut Criterion) {
+    // One second of enemy AI, with the player standing still at the first pose
+    let mut group = c.benchmark_group("enemy_ai");
+    for (level, poses) in POSES {
+        let mut world = load(level);
+        stand_at(&mut world, &poses[0]);
+        group.bench_function(BenchmarkId::new(level, poses[0].name), |b| {
+            b.iter_batched(
+                || (world.enemies.clone(), ProjectilePool::new()),
+                |(mut enemies, mut projectiles)| {
+                    for _ in 0..60 {
+                        for enemy in enemies.iter_mut() {
+                            enemy.update(
+                                world.player.pos,
+                                &world.maze,
+                                &world.heights,
+                                BLOCK_SIZE,
+                                &mut projectiles,
+                                FRAME,
+                            );
+                        }
+                        projectiles.update(&world.maze, BLOCK_SIZE, FRAME);
+                    }
+                    enemies
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, level_loading, enemy_ticks);
+criterion_main!(benches);
@@ -0,0 +1,48 @@
+#[derive(Clone, Copy, PartialEq)]
+pub enum Sequence {
+    Idle,
+    Walk,
+    Attack,
+    Die,
+}
+
+impl Sequence {
+    pub fn from_name(name: &str) -> Option<Sequence> {
+        match name {
+            "idle" => Some(Sequence::Idle),
+            "walk" => Some(Sequence::Walk),
+            "attack" => Some(Sequence::Attack),
+            "die" => Some(Sequence::Die),
+            _ => None,
+        }
+    }
+}
+
+// Per-entity animation state: which sequence is playing and for how long.
+// The sprite sheet turns this into a frame index when drawing.
+#[derive(Clone)]
+pub struct Animation {
+    pub sequence: Sequence,
+    pub time: f32,
+}
+
+impl Animation {
+    pub fn new(sequence: Sequence) -> Self {
+        Animation {
+            sequence,
+            time: 0.0,
+        }
+    }
+
+    // Switch sequence, restarting it only when it actually changes
+    pub fn play(&mut self, sequence: Sequence) {
+        if self.sequence != sequence {
+            self.sequence = sequence;
+            self.time = 0.0;
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.time += dt;
+    }
+}
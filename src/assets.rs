@@ -0,0 +1,227 @@
+use raylib::prelude::*;
+use rodio::buffer::SamplesBuffer;
+use rodio::{Decoder, Source};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File};
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+
+// Missing images are replaced by a checkerboard this big, in squares this big
+const PLACEHOLDER_SIZE: usize = 64;
+const PLACEHOLDER_SQUARE: usize = 8;
+
+// Decoded pixels of an image, row by row
+pub struct Picture {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color>,
+    placeholder: bool,
+}
+
+impl Picture {
+    fn from_image(image: &Image) -> Self {
+        let width = image.width.max(0) as usize;
+        let height = image.height.max(0) as usize;
+        let mut pixels = Vec::with_capacity(width * height);
+        unsafe {
+            let data_ptr = image.data as *const u8;
+            if !data_ptr.is_null() {
+                let data = std::slice::from_raw_parts(data_ptr, width * height * 4);
+                for i in (0..data.len()).step_by(4) {
+                    pixels.push(Color::new(data[i], data[i + 1], data[i + 2], data[i + 3]));
+                }
+            }
+        }
+        Picture {
+            width,
+            height,
+            pixels,
+            placeholder: false,
+        }
+    }
+
+    // Magenta and black squares, hard to mistake for a real texture
+    pub fn placeholder() -> Self {
+        let mut pixels = Vec::with_capacity(PLACEHOLDER_SIZE * PLACEHOLDER_SIZE);
+        for y in 0..PLACEHOLDER_SIZE {
+            for x in 0..PLACEHOLDER_SIZE {
+                let odd = (x / PLACEHOLDER_SQUARE + y / PLACEHOLDER_SQUARE) % 2 == 1;
+                pixels.push(if odd { Color::MAGENTA } else { Color::BLACK });
+            }
+        }
+        Picture {
+            width: PLACEHOLDER_SIZE,
+            height: PLACEHOLDER_SIZE,
+            pixels,
+            placeholder: true,
+        }
+    }
+
+    // Whether the file was missing or unreadable and this was generated instead
+    pub fn is_placeholder(&self) -> bool {
+        self.placeholder
+    }
+
+    // Resample a rectangle of the picture to `to_width` x `to_height` pixels
+    pub fn region(
+        &self,
+        x: usize,
+        y: usize,
+        region_width: usize,
+        region_height: usize,
+        to_width: usize,
+        to_height: usize,
+    ) -> Vec<Color> {
+        let mut colors = Vec::with_capacity(to_width * to_height);
+        for ty in 0..to_height {
+            for tx in 0..to_width {
+                let sx = (x + tx * region_width / to_width).min(self.width.saturating_sub(1));
+                let sy = (y + ty * region_height / to_height).min(self.height.saturating_sub(1));
+                let color = self.pixels.get(sy * self.width + sx).copied();
+                colors.push(color.unwrap_or(Color::GRAY));
+            }
+        }
+        colors
+    }
+}
+
+// Finds, loads and keeps the game's data files. Names like
+// `assets/wall.png` are looked up in the override directories, then the
+// working directory and next to the executable. Debug builds also look in
+// the source tree they were built from, so `cargo run` works from anywhere.
+pub struct Assets {
+    search_paths: Vec<PathBuf>,
+    images: RefCell<HashMap<String, Rc<Picture>>>,
+    sounds: RefCell<HashMap<String, SamplesBuffer>>, // Short effects, decoded
+    music: RefCell<HashMap<String, Arc<[u8]>>>,      // Long tracks, still encoded
+}
+
+impl Default for Assets {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Assets {
+    pub fn new() -> Self {
+        let mut search_paths = vec![PathBuf::from(".")];
+        if let Some(dir) = env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        {
+            search_paths.push(dir);
+        }
+        if cfg!(debug_assertions) {
+            search_paths.push(PathBuf::from(env!("CARGO_MANIFEST_DIR")));
+        }
+
+        Assets {
+            search_paths,
+            images: RefCell::new(HashMap::new()),
+            sounds: RefCell::new(HashMap::new()),
+            music: RefCell::new(HashMap::new()),
+        }
+    }
+
+    // Look in `dir` before anywhere else. Later overrides win over earlier ones.
+    pub fn add_override(&mut self, dir: impl Into<PathBuf>) {
+        self.search_paths.insert(0, dir.into());
+    }
+
+    pub fn search_paths(&self) -> &[PathBuf] {
+        &self.search_paths
+    }
+
+    // The first existing file with this name on the search paths
+    pub fn resolve(&self, name: &str) -> Option<PathBuf> {
+        let path = Path::new(name);
+        if path.is_absolute() {
+            return path.exists().then(|| path.to_path_buf());
+        }
+        self.search_paths
+            .iter()
+            .map(|dir| dir.join(path))
+            .find(|candidate| candidate.exists())
+    }
+
+    // Where to open a data file. Files that can't be found keep their name
+    // so the error opening them says what was missing.
+    pub fn path(&self, name: &str) -> String {
+        self.resolve(name)
+            .map(|path| path.to_string_lossy().into_owned())
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    pub fn read_to_string(&self, name: &str) -> Option<String> {
+        fs::read_to_string(self.resolve(name)?).ok()
+    }
+
+    // An image, decoded the first time it is asked for. Missing images come
+    // back as a placeholder, so check `is_placeholder` to fall back on
+    // something better.
+    pub fn image(&self, name: &str) -> Rc<Picture> {
+        if let Some(picture) = self.images.borrow().get(name) {
+            return picture.clone();
+        }
+
+        let image = self
+            .resolve(name)
+            .and_then(|path| Image::load_image(&path.to_string_lossy()).ok());
+        let picture = Rc::new(match image {
+            Some(image) => Picture::from_image(&image),
+            None => Picture::placeholder(),
+        });
+        self.images
+            .borrow_mut()
+            .insert(name.to_string(), picture.clone());
+        picture
+    }
+
+    // A short sound effect, decoded the first time it is asked for. Missing
+    // sounds are silent.
+    pub fn sound(&self, name: &str) -> SamplesBuffer {
+        if let Some(sound) = self.sounds.borrow().get(name) {
+            return sound.clone();
+        }
+
+        let sound = self.resolve(name).and_then(|path| decode(&path));
+        let sound = sound.unwrap_or_else(|| {
+            println!("No sound found at {} - playing silence", name);
+            SamplesBuffer::new(1, 44100, Vec::new())
+        });
+        self.sounds
+            .borrow_mut()
+            .insert(name.to_string(), sound.clone());
+        sound
+    }
+
+    // A long track such as background music. The file is read once and
+    // decoded as it plays, so it never sits in memory as raw samples. None
+    // when it is missing or can't be decoded.
+    pub fn music(&self, name: &str) -> Option<Decoder<Cursor<Arc<[u8]>>>> {
+        let cached = self.music.borrow().get(name).cloned();
+        let bytes = match cached {
+            Some(bytes) => bytes,
+            None => {
+                let bytes: Arc<[u8]> = fs::read(self.resolve(name)?).ok()?.into();
+                self.music
+                    .borrow_mut()
+                    .insert(name.to_string(), bytes.clone());
+                bytes
+            }
+        };
+        Decoder::new(Cursor::new(bytes)).ok()
+    }
+}
+
+fn decode(path: &Path) -> Option<SamplesBuffer> {
+    let file = BufReader::new(File::open(path).ok()?);
+    let decoder = Decoder::new(file).ok()?;
+    let (channels, sample_rate) = (decoder.channels(), decoder.sample_rate());
+    let samples: Vec<f32> = decoder.collect();
+    Some(SamplesBuffer::new(channels, sample_rate, samples))
+}
@@ -0,0 +1,118 @@
+use crate::assets::Assets;
+use rodio::buffer::SamplesBuffer;
+use rodio::source::SineWave;
+use rodio::{OutputStream, Sink, Source};
+use std::time::Duration;
+
+// Simple audio manager for background music
+pub struct AudioManager {
+    music_playing: bool,
+    output: Option<Output>, // None when the game runs without sound
+}
+
+struct Output {
+    sink: Sink,
+    stream_handle: OutputStream,
+    damage: SamplesBuffer, // Decoded once, played on every hit
+}
+
+impl AudioManager {
+    // Missing sound files play as silence, and so does everything when
+    // there's no audio device
+    pub fn new(assets: &Assets) -> Self {
+        let sh = match rodio::OutputStreamBuilder::open_default_stream() {
+            Ok(sh) => sh,
+            Err(e) => {
+                println!("No audio device ({}) - playing without sound", e);
+                return Self::silent();
+            }
+        };
+
+        // Note that the playback stops when the sink is dropped
+        let s = Sink::connect_new(sh.mixer());
+        match assets.music("assets/background.mp3") {
+            Some(music) => s.append(music),
+            None => println!("No music found at assets/background.mp3 - playing silence"),
+        }
+        s.set_volume(0.3);
+        s.pause();
+
+        AudioManager {
+            music_playing: false,
+            output: Some(Output {
+                sink: s,
+                stream_handle: sh,
+                damage: assets.sound("assets/damage.mp3"),
+            }),
+        }
+    }
+
+    // Keeps track of the music but never opens an audio device
+    pub fn silent() -> Self {
+        AudioManager {
+            music_playing: false,
+            output: None,
+        }
+    }
+
+    pub fn play_music(&mut self) {
+        self.music_playing = true;
+        if let Some(output) = &self.output {
+            output.sink.play();
+        }
+    }
+
+    pub fn pause_music(&mut self) {
+        self.music_playing = false;
+        if let Some(output) = &self.output {
+            output.sink.pause();
+        }
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.music_playing
+    }
+
+    pub fn play_damage_sound(&self) {
+        let Some(output) = &self.output else {
+            return;
+        };
+        let sink = Sink::connect_new(output.stream_handle.mixer());
+        sink.append(output.damage.clone());
+        sink.set_volume(0.3);
+        sink.detach();
+    }
+
+    pub fn play_shot_sound(&self) {
+        let Some(output) = &self.output else {
+            return;
+        };
+        // Low, short thump for the player's weapon
+        let sink = Sink::connect_new(output.stream_handle.mixer());
+        sink.append(
+            SineWave::new(110.0)
+                .take_duration(Duration::from_millis(70))
+                .amplify(0.4),
+        );
+        sink.detach();
+    }
+
+    pub fn play_checkpoint_sound(&self) {
+        let Some(output) = &self.output else {
+            return;
+        };
+        // Short rising two-tone chime, generated so no extra asset is needed
+        let sink = Sink::connect_new(output.stream_handle.mixer());
+        sink.append(
+            SineWave::new(660.0)
+                .take_duration(Duration::from_millis(120))
+                .amplify(0.2),
+        );
+        sink.append(
+            SineWave::new(880.0)
+                .take_duration(Duration::from_millis(220))
+                .amplify(0.2),
+        );
+        sink.detach();
+    }
+}
@@ -0,0 +1,436 @@
+use raylib::prelude::*;
+use std::fs;
+use std::io::ErrorKind;
+use std::str::FromStr;
+
+// The drawing primitives the renderers use. The game draws through raylib,
+// while a SoftwareCanvas draws into memory so whole frames can be rendered
+// without a window, for screenshots and tests.
+pub trait Canvas {
+    fn clear_background(&mut self, color: Color);
+    fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color);
+    fn draw_circle(&mut self, x: i32, y: i32, radius: f32, color: Color);
+    fn draw_circle_lines(&mut self, x: i32, y: i32, radius: f32, color: Color);
+    fn draw_line(&mut self, start: Vector2, end: Vector2, thickness: f32, color: Color);
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color);
+    fn measure_text(&self, text: &str, font_size: i32) -> i32;
+
+    // Copy a block of pixels, `width` to a row, with its top-left corner at (x, y)
+    fn draw_pixels(&mut self, x: i32, y: i32, width: usize, pixels: &[Color]);
+
+    // Stretch a block of pixels, `width` to a row, over a rectangle
+    fn draw_pixels_scaled(
+        &mut self,
+        dest: Rectangle,
+        width: usize,
+        pixels: &[Color],
+        scaling: Scaling,
+    ) {
+        let (dest_width, dest_height) = (dest.width as usize, dest.height as usize);
+        let stretched = scale_pixels(width, pixels, dest_width, dest_height, scaling);
+        self.draw_pixels(dest.x as i32, dest.y as i32, dest_width, &stretched);
+    }
+
+    // Seconds since the game started, which drives pulsing and bobbing
+    fn time(&self) -> f64;
+    fn fps(&self) -> u32;
+}
+
+// How a small picture is stretched over a bigger area
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Scaling {
+    Pixel,  // Every pixel becomes a sharp-edged block
+    Smooth, // Colours blend across the gaps between pixels
+}
+
+impl FromStr for Scaling {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "pixel" => Ok(Scaling::Pixel),
+            "smooth" => Ok(Scaling::Smooth),
+            _ => Err(format!("unknown scaling '{}', expected pixel or smooth", s)),
+        }
+    }
+}
+
+// Draws to the window. Big blocks of pixels, such as the 3D view, go
+// through a texture that is kept between frames and only remade when the
+// block changes size.
+pub struct RaylibCanvas<'a> {
+    d: RaylibDrawHandle<'a>,
+    thread: &'a RaylibThread,
+    texture: &'a mut Option<Texture2D>,
+}
+
+impl<'a> RaylibCanvas<'a> {
+    pub fn new(
+        d: RaylibDrawHandle<'a>,
+        thread: &'a RaylibThread,
+        texture: &'a mut Option<Texture2D>,
+    ) -> Self {
+        RaylibCanvas { d, thread, texture }
+    }
+
+    // Upload the pixels to the kept texture and stretch it over `dest`, or
+    // false if there is no texture to draw with
+    fn draw_texture(
+        &mut self,
+        dest: Rectangle,
+        width: usize,
+        pixels: &[Color],
+        scaling: Scaling,
+    ) -> bool {
+        let height = (pixels.len() / width) as i32;
+        let width = width as i32;
+        if self
+            .texture
+            .as_ref()
+            .is_none_or(|texture| texture.width != width || texture.height != height)
+        {
+            let image = Image::gen_image_color(width, height, Color::BLACK);
+            *self.texture = self.d.load_texture_from_image(self.thread, &image).ok();
+        }
+
+        let Some(texture) = self.texture.as_mut() else {
+            return false;
+        };
+        let bytes: Vec<u8> = pixels
+            .iter()
+            .flat_map(|color| [color.r, color.g, color.b, color.a])
+            .collect();
+        if texture.update_texture(&bytes).is_err() {
+            return false;
+        }
+        texture.set_texture_filter(
+            self.thread,
+            match scaling {
+                Scaling::Pixel => TextureFilter::TEXTURE_FILTER_POINT,
+                Scaling::Smooth => TextureFilter::TEXTURE_FILTER_BILINEAR,
+            },
+        );
+        let source = Rectangle::new(0.0, 0.0, width as f32, height as f32);
+        self.d
+            .draw_texture_pro(&*texture, source, dest, Vector2::zero(), 0.0, Color::WHITE);
+        true
+    }
+}
+
+impl Canvas for RaylibCanvas<'_> {
+    fn clear_background(&mut self, color: Color) {
+        self.d.clear_background(color);
+    }
+
+    fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        self.d.draw_rectangle(x, y, width, height, color);
+    }
+
+    fn draw_circle(&mut self, x: i32, y: i32, radius: f32, color: Color) {
+        self.d.draw_circle(x, y, radius, color);
+    }
+
+    fn draw_circle_lines(&mut self, x: i32, y: i32, radius: f32, color: Color) {
+        self.d.draw_circle_lines(x, y, radius, color);
+    }
+
+    fn draw_line(&mut self, start: Vector2, end: Vector2, thickness: f32, color: Color) {
+        self.d.draw_line_ex(start, end, thickness, color);
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
+        self.d.draw_text(text, x, y, font_size, color);
+    }
+
+    fn measure_text(&self, text: &str, font_size: i32) -> i32 {
+        self.d.measure_text(text, font_size)
+    }
+
+    fn draw_pixels(&mut self, x: i32, y: i32, width: usize, pixels: &[Color]) {
+        let height = pixels.len() / width.max(1);
+        let dest = Rectangle::new(x as f32, y as f32, width as f32, height as f32);
+        if width == 0 || pixels.is_empty() || self.draw_texture(dest, width, pixels, Scaling::Pixel)
+        {
+            return;
+        }
+
+        // Without a texture, one rectangle per run of matching pixels keeps
+        // the draw calls down
+        for (row, line) in pixels.chunks(width).enumerate() {
+            let mut start = 0;
+            for i in 1..=line.len() {
+                if i == line.len() || line[i] != line[start] {
+                    let run = (i - start) as i32;
+                    self.d
+                        .draw_rectangle(x + start as i32, y + row as i32, run, 1, line[start]);
+                    start = i;
+                }
+            }
+        }
+    }
+
+    fn draw_pixels_scaled(
+        &mut self,
+        dest: Rectangle,
+        width: usize,
+        pixels: &[Color],
+        scaling: Scaling,
+    ) {
+        // The graphics card stretches the texture for free; without one the
+        // pixels are stretched here first
+        if width == 0 || pixels.is_empty() || self.draw_texture(dest, width, pixels, scaling) {
+            return;
+        }
+        let (dest_width, dest_height) = (dest.width as usize, dest.height as usize);
+        let stretched = scale_pixels(width, pixels, dest_width, dest_height, scaling);
+        self.draw_pixels(dest.x as i32, dest.y as i32, dest_width, &stretched);
+    }
+
+    fn time(&self) -> f64 {
+        self.d.get_time()
+    }
+
+    fn fps(&self) -> u32 {
+        self.d.get_fps()
+    }
+}
+
+// Stretch a block of pixels, `width` to a row, to a new size
+pub fn scale_pixels(
+    width: usize,
+    pixels: &[Color],
+    to_width: usize,
+    to_height: usize,
+    scaling: Scaling,
+) -> Vec<Color> {
+    if width == 0 || pixels.is_empty() {
+        return vec![Color::BLACK; to_width * to_height];
+    }
+    let height = pixels.len() / width;
+    let at = |x: usize, y: usize| pixels[y.min(height - 1) * width + x.min(width - 1)];
+
+    let mut stretched = Vec::with_capacity(to_width * to_height);
+    for y in 0..to_height {
+        for x in 0..to_width {
+            let color = match scaling {
+                Scaling::Pixel => at(x * width / to_width, y * height / to_height),
+                Scaling::Smooth => {
+                    // Mix the four pixels around the matching point of the source
+                    let sx = ((x as f32 + 0.5) * width as f32 / to_width as f32 - 0.5).max(0.0);
+                    let sy = ((y as f32 + 0.5) * height as f32 / to_height as f32 - 0.5).max(0.0);
+                    let (x0, y0) = (sx as usize, sy as usize);
+                    let (fx, fy) = (sx.fract(), sy.fract());
+                    let mix = |a: u8, b: u8, c: u8, d: u8| {
+                        let top = a as f32 + (b as f32 - a as f32) * fx;
+                        let bottom = c as f32 + (d as f32 - c as f32) * fx;
+                        (top + (bottom - top) * fy).round() as u8
+                    };
+                    let (a, b) = (at(x0, y0), at(x0 + 1, y0));
+                    let (c, d) = (at(x0, y0 + 1), at(x0 + 1, y0 + 1));
+                    Color::new(
+                        mix(a.r, b.r, c.r, d.r),
+                        mix(a.g, b.g, c.g, d.g),
+                        mix(a.b, b.b, c.b, d.b),
+                        mix(a.a, b.a, c.a, d.a),
+                    )
+                }
+            };
+            stretched.push(color);
+        }
+    }
+    stretched
+}
+
+// One colour laid over another, mixed by the top colour's alpha
+pub fn blend(below: Color, color: Color) -> Color {
+    let alpha = color.a as f32 / 255.0;
+    let mix = |top: u8, bottom: u8| (top as f32 * alpha + bottom as f32 * (1.0 - alpha)) as u8;
+    Color::new(
+        mix(color.r, below.r),
+        mix(color.g, below.g),
+        mix(color.b, below.b),
+        (color.a as f32 + below.a as f32 * (1.0 - alpha)) as u8,
+    )
+}
+
+// A frame held in memory. Text has no font here, so each character is drawn
+// as a solid block the size raylib's default font would give it.
+pub struct SoftwareCanvas {
+    width: i32,
+    height: i32,
+    pixels: Vec<Color>,
+    time: f64,
+}
+
+impl SoftwareCanvas {
+    pub fn new(width: i32, height: i32) -> Self {
+        SoftwareCanvas {
+            width,
+            height,
+            pixels: vec![Color::BLACK; (width.max(0) * height.max(0)) as usize],
+            time: 0.0,
+        }
+    }
+
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    pub fn pixel(&self, x: i32, y: i32) -> Option<Color> {
+        self.index(x, y).map(|i| self.pixels[i])
+    }
+
+    // Fix the clock so animated parts of a frame come out the same every time
+    pub fn set_time(&mut self, time: f64) {
+        self.time = time;
+    }
+
+    // Save the frame as an image, in whatever format the extension names.
+    // raylib doesn't say whether the export worked, so any old file is
+    // removed first and the new one has to be there afterwards.
+    pub fn export(&self, path: &str) -> Result<(), String> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(format!("{}: {}", path, e)),
+            _ => {}
+        }
+
+        let mut image = Image::gen_image_color(self.width, self.height, Color::BLACK);
+        for (i, &color) in self.pixels.iter().enumerate() {
+            let i = i as i32;
+            image.draw_pixel(i % self.width, i / self.width, color);
+        }
+        image.export_image(path);
+
+        if fs::metadata(path).is_ok() {
+            Ok(())
+        } else {
+            Err(format!("Can't save frame to {}", path))
+        }
+    }
+
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        (x >= 0 && y >= 0 && x < self.width && y < self.height)
+            .then_some((y * self.width + x) as usize)
+    }
+
+    // Draw one pixel over what is already there, mixing by the colour's alpha
+    fn blend(&mut self, x: i32, y: i32, color: Color) {
+        let Some(i) = self.index(x, y) else {
+            return;
+        };
+        self.pixels[i] = blend(self.pixels[i], color);
+    }
+
+    fn glyph_width(font_size: i32) -> i32 {
+        font_size / 2
+    }
+
+    fn glyph_spacing(font_size: i32) -> i32 {
+        (font_size / 10).max(1)
+    }
+}
+
+impl Canvas for SoftwareCanvas {
+    fn clear_background(&mut self, color: Color) {
+        self.pixels.fill(color);
+    }
+
+    fn draw_rectangle(&mut self, x: i32, y: i32, width: i32, height: i32, color: Color) {
+        for py in y.max(0)..(y + height).min(self.height) {
+            for px in x.max(0)..(x + width).min(self.width) {
+                self.blend(px, py, color);
+            }
+        }
+    }
+
+    fn draw_circle(&mut self, x: i32, y: i32, radius: f32, color: Color) {
+        let r = radius.ceil() as i32;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                if ((dx * dx + dy * dy) as f32) <= radius * radius {
+                    self.blend(x + dx, y + dy, color);
+                }
+            }
+        }
+    }
+
+    fn draw_circle_lines(&mut self, x: i32, y: i32, radius: f32, color: Color) {
+        let r = radius.ceil() as i32 + 1;
+        for dy in -r..=r {
+            for dx in -r..=r {
+                let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                if (distance - radius).abs() < 0.5 {
+                    self.blend(x + dx, y + dy, color);
+                }
+            }
+        }
+    }
+
+    fn draw_line(&mut self, start: Vector2, end: Vector2, thickness: f32, color: Color) {
+        // Every pixel whose centre is within half the thickness of the segment
+        let half = (thickness / 2.0).max(0.5);
+        let segment = end - start;
+        let length_sqr = segment.x * segment.x + segment.y * segment.y;
+        let min_x = (start.x.min(end.x) - half).floor() as i32;
+        let max_x = (start.x.max(end.x) + half).ceil() as i32;
+        let min_y = (start.y.min(end.y) - half).floor() as i32;
+        let max_y = (start.y.max(end.y) + half).ceil() as i32;
+
+        for y in min_y.max(0)..=max_y.min(self.height - 1) {
+            for x in min_x.max(0)..=max_x.min(self.width - 1) {
+                let point = Vector2::new(x as f32 + 0.5, y as f32 + 0.5) - start;
+                let t = if length_sqr > 0.0 {
+                    ((point.x * segment.x + point.y * segment.y) / length_sqr).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let offset = point - segment * t;
+                if offset.x * offset.x + offset.y * offset.y <= half * half {
+                    self.blend(x, y, color);
+                }
+            }
+        }
+    }
+
+    fn draw_text(&mut self, text: &str, x: i32, y: i32, font_size: i32, color: Color) {
+        let glyph = Self::glyph_width(font_size);
+        let step = glyph + Self::glyph_spacing(font_size);
+        for (i, ch) in text.chars().enumerate() {
+            if !ch.is_whitespace() {
+                self.draw_rectangle(x + i as i32 * step, y, glyph, font_size * 7 / 10, color);
+            }
+        }
+    }
+
+    fn measure_text(&self, text: &str, font_size: i32) -> i32 {
+        let count = text.chars().count() as i32;
+        if count == 0 {
+            return 0;
+        }
+        count * Self::glyph_width(font_size) + (count - 1) * Self::glyph_spacing(font_size)
+    }
+
+    fn draw_pixels(&mut self, x: i32, y: i32, width: usize, pixels: &[Color]) {
+        for (i, &color) in pixels.iter().enumerate() {
+            let column = (i % width.max(1)) as i32;
+            let row = (i / width.max(1)) as i32;
+            self.blend(x + column, y + row, color);
+        }
+    }
+
+    fn time(&self) -> f64 {
+        self.time
+    }
+
+    fn fps(&self) -> u32 {
+        0
+    }
+}
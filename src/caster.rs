@@ -1,5 +1,10 @@
+use crate::heights::HeightMap;
 use crate::maze::Maze;
+use crate::panes::Panes;
 use crate::player::Player;
+use crate::portals::{Portals, rotate};
+use crate::thin_walls::ThinWall;
+use raylib::prelude::*;
 
 pub struct Intersect {
     pub distance: f32,
@@ -33,7 +38,17 @@ pub fn cast_ray(maze: &Maze, player: &Player, a: f32, block_size: usize) -> Inte
             };
         }
 
-        if maze[j][i] != ' ' {
+        // Thin walls are hit exactly; rays that miss the line carry on through the cell
+        if let Some(wall) = ThinWall::from_char(maze[j][i]) {
+            if let Some((distance, tx)) = wall.hit(i, j, block_size_f, player.pos, a) {
+                return Intersect {
+                    distance,
+                    impact: maze[j][i],
+                    tx,
+                    perpendicular_distance: distance * (a - player.a).cos(),
+                };
+            }
+        } else if maze[j][i] != ' ' {
             let mut exact_d = d - STEP_SIZE;
             let mut exact_x = player.pos.x + exact_d * cos_a;
             let mut exact_y = player.pos.y + exact_d * sin_a;
@@ -49,23 +64,7 @@ pub fn cast_ray(maze: &Maze, player: &Player, a: f32, block_size: usize) -> Inte
                 }
             }
 
-            let cell_x = exact_x - (i as f32 * block_size_f);
-            let cell_y = exact_y - (j as f32 * block_size_f);
-
-            // Better wall detection and texture coordinate calculation
-            let tx = if cell_x <= 1.0 {
-                // Left wall
-                ((cell_y / block_size_f) * 128.0) as usize
-            } else if cell_x >= block_size_f - 1.0 {
-                // Right wall
-                ((cell_y / block_size_f) * 128.0) as usize
-            } else if cell_y <= 1.0 {
-                // Top wall
-                ((cell_x / block_size_f) * 128.0) as usize
-            } else {
-                // Bottom wall
-                ((cell_x / block_size_f) * 128.0) as usize
-            };
+            let tx = texture_x(exact_x, exact_y, i, j, block_size_f);
 
             // Calculate perpendicular distance to avoid fisheye
             let angle_diff = a - player.a;
@@ -91,3 +90,255 @@ pub fn cast_ray(maze: &Maze, player: &Player, a: f32, block_size: usize) -> Inte
         }
     }
 }
+
+// What a layer along the ray is
+pub enum LayerKind {
+    Step,            // The ground or a wall top changes height
+    Pane(f32),       // A see-through wall of this height
+    Redirect(Color), // A mirror or portal; everything further on is seen in it, washed with this colour
+}
+
+// One place along a ray where what it sees changes
+pub struct Layer {
+    pub intersect: Intersect,
+    pub top: f32, // Height of the ground or wall top the ray is over from here on
+    pub kind: LayerKind,
+    pub light_pos: Vector2, // Just in front of the hit, where its light is sampled
+}
+
+// Like cast_ray, but keeps going past walls and platforms shorter than the
+// tallest thing in the level, through see-through walls and the open side of
+// thin walls, off mirrors and through portals. Returns every point where the
+// ray enters a cell of a different height, a pane, a mirror or a portal,
+// nearest first. The last entry is the wall that ends the ray.
+pub fn cast_ray_layers(
+    maze: &Maze,
+    heights: &HeightMap,
+    panes: &Panes,
+    portals: &Portals,
+    player: &Player,
+    a: f32,
+    block_size: usize,
+) -> Vec<Layer> {
+    const MAX_DISTANCE: f32 = 5000.0;
+    const STEP_SIZE: f32 = 1.0;
+    const MAX_REDIRECTS: usize = 4; // Facing mirrors would otherwise reflect forever
+    const PORTAL_COLOR: Color = Color::new(150, 90, 255, 50);
+
+    let block_size_f = block_size as f32;
+    let perpendicular = (a - player.a).cos();
+
+    let cell_of = |pos: Vector2| {
+        if pos.x < 0.0 || pos.y < 0.0 {
+            return None;
+        }
+        Some((
+            (pos.x / block_size_f) as usize,
+            (pos.y / block_size_f) as usize,
+        ))
+    };
+    let impact_of = |cell: Option<(usize, usize)>| {
+        cell.and_then(|(i, j)| maze.get(j).and_then(|row| row.get(i)))
+            .copied()
+            .unwrap_or('#')
+    };
+    let is_see_through = |cell: Option<(usize, usize)>| panes.is_see_through(impact_of(cell));
+    // Panes don't change the height the ray is over, it just passes through them
+    let top_of = |cell: Option<(usize, usize)>, current_top: f32| match cell {
+        _ if is_see_through(cell) => current_top,
+        Some((i, j)) => heights.top(i, j),
+        None => heights.max_top(),
+    };
+    let hit_at = |pos: Vector2, distance: f32| {
+        let cell = cell_of(pos);
+        let tx = cell.map_or(0, |(i, j)| texture_x(pos.x, pos.y, i, j, block_size_f));
+        Intersect {
+            distance,
+            impact: impact_of(cell),
+            tx: tx.min(127),
+            perpendicular_distance: distance * perpendicular,
+        }
+    };
+    // Narrow down where something changes in the last step along a segment
+    let refine =
+        |origin: Vector2, direction: Vector2, s: f32, changed: &dyn Fn(Vector2) -> bool| {
+            let mut exact = s - STEP_SIZE;
+            for _ in 0..5 {
+                exact += 0.2;
+                if changed(origin + direction * exact) {
+                    break;
+                }
+            }
+            exact
+        };
+
+    // The ray is a chain of straight segments: it bends at mirrors and jumps
+    // at portals. Distances keep counting along the whole chain, which is
+    // how far away everything seen in a mirror or portal looks.
+    let mut origin = player.pos;
+    let mut direction = Vector2::new(a.cos(), a.sin());
+    let mut travelled = 0.0; // Length of the segments before this one
+    let mut redirects = 0;
+
+    // Thin walls are full height and end the ray when it hits the line
+    let thin_wall_hit =
+        |cell: Option<(usize, usize)>, origin: Vector2, direction: Vector2, travelled: f32| {
+            let (i, j) = cell?;
+            let angle = direction.y.atan2(direction.x);
+            let (t, tx) =
+                ThinWall::from_char(impact_of(cell))?.hit(i, j, block_size_f, origin, angle)?;
+            let distance = travelled + t;
+            Some(Layer {
+                intersect: Intersect {
+                    distance,
+                    impact: impact_of(cell),
+                    tx,
+                    perpendicular_distance: distance * perpendicular,
+                },
+                top: block_size_f,
+                kind: LayerKind::Step,
+                light_pos: origin + direction * (t - 2.0).max(0.0),
+            })
+        };
+
+    let mut layers = Vec::new();
+    let mut previous_cell = cell_of(origin);
+    let mut current_top = top_of(previous_cell, 0.0);
+    if let Some(layer) = thin_wall_hit(previous_cell, origin, direction, travelled) {
+        layers.push(layer);
+        return layers;
+    }
+    let mut s = 0.0;
+
+    loop {
+        s += STEP_SIZE;
+
+        if travelled + s > MAX_DISTANCE {
+            layers.push(Layer {
+                intersect: Intersect {
+                    distance: travelled + s,
+                    impact: ' ',
+                    tx: 0,
+                    perpendicular_distance: (travelled + s) * perpendicular,
+                },
+                top: heights.max_top(),
+                kind: LayerKind::Step,
+                light_pos: origin + direction * s,
+            });
+            return layers;
+        }
+
+        let cell = cell_of(origin + direction * s);
+        if cell == previous_cell {
+            continue;
+        }
+
+        if let Some(layer) = thin_wall_hit(cell, origin, direction, travelled) {
+            layers.push(layer);
+            return layers;
+        }
+
+        let exact = refine(origin, direction, s, &|pos| cell_of(pos) != previous_cell);
+        let entry = origin + direction * exact;
+        let light_pos = origin + direction * (exact - 2.0).max(0.0);
+
+        // Mirrors send the ray back off the face it hit, carrying on from
+        // just in front of it
+        if redirects < MAX_REDIRECTS
+            && let Some(mirror) = panes.mirror(impact_of(cell))
+            && let (Some((i, j)), Some((previous_i, previous_j))) = (cell, previous_cell)
+        {
+            layers.push(Layer {
+                intersect: hit_at(entry, travelled + exact),
+                top: current_top,
+                kind: LayerKind::Redirect(mirror.tint()),
+                light_pos,
+            });
+            let back = (exact - 0.2).max(0.0);
+            origin += direction * back;
+            if i != previous_i {
+                direction.x = -direction.x;
+            }
+            if j != previous_j {
+                direction.y = -direction.y;
+            }
+            travelled += back;
+            s = 0.0;
+            redirects += 1;
+            continue;
+        }
+
+        // Portals carry the ray over to their partner cell, turned to match
+        if redirects < MAX_REDIRECTS
+            && let Some((i, j)) = cell
+            && let Some((partner, turn)) = portals.link((i, j))
+        {
+            layers.push(Layer {
+                intersect: hit_at(entry, travelled + exact),
+                top: current_top,
+                kind: LayerKind::Redirect(PORTAL_COLOR),
+                light_pos,
+            });
+            origin = Portals::carry((i, j), partner, turn, entry, block_size_f);
+            direction = rotate(direction, turn);
+            travelled += exact;
+            s = 0.0;
+            redirects += 1;
+            previous_cell = Some(partner);
+            current_top = top_of(previous_cell, current_top);
+            continue;
+        }
+
+        if is_see_through(cell) {
+            let (i, j) = cell.unwrap();
+            layers.push(Layer {
+                intersect: hit_at(entry, travelled + exact),
+                top: current_top,
+                kind: LayerKind::Pane(heights.top(i, j)),
+                light_pos,
+            });
+        }
+        previous_cell = cell;
+
+        let top = top_of(cell, current_top);
+        if top == current_top {
+            continue;
+        }
+
+        let exact = refine(origin, direction, s, &|pos| {
+            top_of(cell_of(pos), current_top) != current_top
+        });
+        layers.push(Layer {
+            intersect: hit_at(origin + direction * exact, travelled + exact),
+            top,
+            kind: LayerKind::Step,
+            light_pos: origin + direction * (exact - 2.0).max(0.0),
+        });
+        current_top = top;
+
+        if top >= heights.max_top() {
+            return layers;
+        }
+    }
+}
+
+// Horizontal texture coordinate of a hit on the edge of cell (i, j)
+fn texture_x(exact_x: f32, exact_y: f32, i: usize, j: usize, block_size_f: f32) -> usize {
+    let cell_x = exact_x - (i as f32 * block_size_f);
+    let cell_y = exact_y - (j as f32 * block_size_f);
+
+    // Better wall detection and texture coordinate calculation
+    if cell_x <= 1.0 {
+        // Left wall
+        ((cell_y / block_size_f) * 128.0) as usize
+    } else if cell_x >= block_size_f - 1.0 {
+        // Right wall
+        ((cell_y / block_size_f) * 128.0) as usize
+    } else if cell_y <= 1.0 {
+        // Top wall
+        ((cell_x / block_size_f) * 128.0) as usize
+    } else {
+        // Bottom wall
+        ((cell_x / block_size_f) * 128.0) as usize
+    }
+}
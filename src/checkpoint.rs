@@ -0,0 +1,16 @@
+use raylib::prelude::*;
+
+#[derive(Clone)]
+pub struct Checkpoint {
+    pub pos: Vector2,
+    pub activated: bool,
+}
+
+impl Checkpoint {
+    pub fn new(x: f32, y: f32) -> Self {
+        Checkpoint {
+            pos: Vector2::new(x, y),
+            activated: false,
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use raylib::prelude::*;
+use std::ops::Range;
+
+// A slice of a see-through wall, drawn later with the sprites so that both
+// are layered by distance
+pub struct PaneSlice {
+    pub columns: Range<i32>,
+    pub distance: f32,
+    pub runs: Vec<(Range<i32>, Color)>, // Screen rows and colour, holes left out
+}
+
+// Per-column depth information left by the wall pass so sprites can be
+// hidden behind walls. Short walls and platforms only hide what is below
+// their top edge, so they are kept as ledges next to the full walls.
+pub struct DepthBuffer {
+    walls: Vec<f32>,              // Distance to the wall that ends the ray
+    ledges: Vec<Vec<(f32, i32)>>, // Distance and top screen row of everything shorter in front of it
+    panes: Vec<PaneSlice>,
+}
+
+impl Default for DepthBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DepthBuffer {
+    pub fn new() -> Self {
+        DepthBuffer {
+            walls: Vec::new(),
+            ledges: Vec::new(),
+            panes: Vec::new(),
+        }
+    }
+
+    pub fn clear(&mut self, width: usize) {
+        self.walls.clear();
+        self.walls.resize(width, f32::MAX);
+        self.ledges.resize_with(width, Vec::new);
+        for ledges in self.ledges.iter_mut() {
+            ledges.clear();
+        }
+        self.panes.clear();
+    }
+
+    pub fn width(&self) -> usize {
+        self.walls.len()
+    }
+
+    pub fn set_wall(&mut self, x: usize, distance: f32) {
+        if let Some(wall) = self.walls.get_mut(x) {
+            *wall = distance;
+        }
+    }
+
+    pub fn add_ledge(&mut self, x: usize, distance: f32, top_row: i32) {
+        if let Some(ledges) = self.ledges.get_mut(x) {
+            ledges.push((distance, top_row));
+        }
+    }
+
+    pub fn add_pane(&mut self, pane: PaneSlice) {
+        self.panes.push(pane);
+    }
+
+    pub fn panes(&self) -> &[PaneSlice] {
+        &self.panes
+    }
+
+    // Screen row where something at `distance` disappears behind a ledge in
+    // column x (i32::MAX when nothing covers it), or None when a wall hides
+    // the whole column
+    pub fn visible_until(&self, x: usize, distance: f32) -> Option<i32> {
+        if distance >= self.walls[x] {
+            return None;
+        }
+        Some(
+            self.ledges[x]
+                .iter()
+                .filter(|&&(ledge_distance, _)| ledge_distance < distance)
+                .map(|&(_, top_row)| top_row)
+                .min()
+                .unwrap_or(i32::MAX),
+        )
+    }
+}
@@ -1,16 +1,298 @@
+use crate::animation::{Animation, Sequence};
+use crate::heights::HeightMap;
+use crate::maze::{Maze, has_line_of_sight};
+use crate::player::{can_stand, ground_height};
+use crate::projectile::ProjectilePool;
+use crate::thin_walls::ThinWall;
 use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::fs;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Behaviour {
+    Wander, // Roams the corridors, drifts toward the player when close
+    Chase,  // Waits at its post and runs at the player once detected
+    Turret, // Never moves, shoots at the player when it has line of sight
+    Ghost,  // Floats straight at the player, ignoring walls
+}
+
+impl Behaviour {
+    fn from_name(name: &str) -> Option<Behaviour> {
+        match name {
+            "wander" => Some(Behaviour::Wander),
+            "chase" => Some(Behaviour::Chase),
+            "turret" => Some(Behaviour::Turret),
+            "ghost" => Some(Behaviour::Ghost),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct EnemyType {
+    pub key: char,
+    pub name: String,
+    pub sprite: String,
+    pub speed: f32,
+    pub damage: i32,
+    pub health: i32,
+    pub detection_radius: f32,
+    pub behaviour: Behaviour,
+}
+
+// How long a defeated enemy takes to collapse before disappearing
+pub const DEATH_TIME: f32 = 0.6;
+
+// Characters levels already use for open floor, the finish, checkpoints,
+// ammo and the walls of the shipped and generated mazes, plus the projectile
+// sprite. Thin walls are reserved too.
+const RESERVED_KEYS: [char; 13] = [
+    ' ', '.', '_', 'w', 'W', 'c', 'C', 'a', 'A', '+', '-', '|', '*',
+];
 
 #[derive(Clone)]
 pub struct Enemy {
     pub pos: Vector2,
+    pub spawn_pos: Vector2,
     pub texture_key: char,
+    pub speed: f32,
+    pub damage: i32,
+    pub health: i32,
+    pub detection_radius: f32,
+    pub behaviour: Behaviour,
+    pub heading: f32, // Facing direction, also the direction of travel
+    pub fire_cooldown: f32,
+    pub death_timer: f32,
+    pub animation: Animation,
 }
 
 impl Enemy {
-    pub fn new(x: f32, y: f32) -> Self {
+    pub fn new(x: f32, y: f32, enemy_type: &EnemyType) -> Self {
         Enemy {
             pos: Vector2::new(x, y),
-            texture_key: 'e',
+            spawn_pos: Vector2::new(x, y),
+            texture_key: enemy_type.key,
+            speed: enemy_type.speed,
+            damage: enemy_type.damage,
+            health: enemy_type.health,
+            detection_radius: enemy_type.detection_radius,
+            behaviour: enemy_type.behaviour,
+            heading: 0.0,
+            fire_cooldown: 0.0,
+            death_timer: 0.0,
+            animation: Animation::new(Sequence::Walk),
         }
     }
+
+    pub fn is_alive(&self) -> bool {
+        self.health > 0
+    }
+
+    // Dead and done playing the death animation
+    pub fn is_gone(&self) -> bool {
+        !self.is_alive() && self.death_timer <= 0.0
+    }
+
+    pub fn take_damage(&mut self, amount: i32) {
+        if !self.is_alive() {
+            return;
+        }
+
+        self.health -= amount;
+        if self.health <= 0 {
+            self.death_timer = DEATH_TIME;
+            self.animation.play(Sequence::Die);
+        }
+    }
+
+    // Send the enemy back to where the level placed it (defeated enemies stay dead)
+    pub fn reset(&mut self) {
+        self.pos = self.spawn_pos;
+        self.heading = 0.0;
+        self.fire_cooldown = 0.0;
+        if self.is_alive() {
+            self.animation = Animation::new(Sequence::Walk);
+        }
+    }
+
+    pub fn update(
+        &mut self,
+        player_pos: Vector2,
+        maze: &Maze,
+        heights: &HeightMap,
+        block_size: usize,
+        projectiles: &mut ProjectilePool,
+        dt: f32,
+    ) {
+        const COLLISION_MARGIN: f32 = 10.0;
+        const FIRE_INTERVAL: f32 = 1.5; // Seconds between turret shots
+        const ATTACK_RANGE: f32 = 80.0; // Close enough to play the attack animation
+        const ATTACK_TIME: f32 = 0.3; // How long a turret shows its attack frames
+
+        self.animation.update(dt);
+
+        if !self.is_alive() {
+            self.death_timer -= dt;
+            return;
+        }
+
+        let dx = player_pos.x - self.pos.x;
+        let dy = player_pos.y - self.pos.y;
+        let player_distance = (dx * dx + dy * dy).sqrt();
+        let detected = player_distance < self.detection_radius;
+        let step = self.speed * dt;
+        let start_pos = self.pos;
+
+        match self.behaviour {
+            Behaviour::Turret => {
+                if self.fire_cooldown > 0.0 {
+                    self.fire_cooldown -= dt;
+                }
+
+                // Turrets turn to track the player once they notice them
+                if detected {
+                    self.heading = dy.atan2(dx);
+                }
+
+                if detected
+                    && self.fire_cooldown <= 0.0
+                    && has_line_of_sight(maze, self.pos, player_pos, block_size)
+                {
+                    projectiles.spawn(self.pos, self.heading, self.damage);
+                    self.fire_cooldown = FIRE_INTERVAL;
+                }
+            }
+            Behaviour::Ghost => {
+                if detected && player_distance > 1.0 {
+                    self.heading = dy.atan2(dx);
+                    let new_x = self.pos.x + step * dx / player_distance;
+                    let new_y = self.pos.y + step * dy / player_distance;
+
+                    // Ghosts pass through walls but never leave the maze
+                    let max_x = (maze[0].len() * block_size) as f32;
+                    let max_y = (maze.len() * block_size) as f32;
+                    self.pos.x = new_x.clamp(0.0, max_x - 1.0);
+                    self.pos.y = new_y.clamp(0.0, max_y - 1.0);
+                }
+            }
+            Behaviour::Chase => {
+                if detected {
+                    self.heading = dy.atan2(dx);
+                    self.slide(step, maze, heights, block_size, COLLISION_MARGIN);
+                }
+            }
+            Behaviour::Wander => {
+                if detected {
+                    self.heading = dy.atan2(dx);
+                    self.slide(step, maze, heights, block_size, COLLISION_MARGIN);
+                } else if !self.slide(step, maze, heights, block_size, COLLISION_MARGIN) {
+                    // Blocked: try turning right, then left, then back the way we came
+                    let z = ground_height(self.pos.x, self.pos.y, heights, COLLISION_MARGIN);
+                    for turn in [PI / 2.0, -PI / 2.0, PI] {
+                        let heading = self.heading + turn;
+                        let new_x = self.pos.x + step * heading.cos();
+                        let new_y = self.pos.y + step * heading.sin();
+                        if can_stand(new_x, new_y, z, maze, heights, block_size, COLLISION_MARGIN) {
+                            self.heading = heading;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        // Pick the animation that matches what the enemy is doing
+        let attacking = if self.behaviour == Behaviour::Turret {
+            self.fire_cooldown > FIRE_INTERVAL - ATTACK_TIME
+        } else {
+            player_distance < ATTACK_RANGE
+        };
+        let moving = self.pos != start_pos;
+
+        if attacking {
+            self.animation.play(Sequence::Attack);
+        } else if moving {
+            self.animation.play(Sequence::Walk);
+        } else {
+            self.animation.play(Sequence::Idle);
+        }
+    }
+
+    // Move along the heading, sliding along walls; returns false if fully
+    // blocked. Enemies climb steps and short ledges the way the player does,
+    // but can't jump onto anything taller.
+    fn slide(
+        &mut self,
+        step: f32,
+        maze: &Maze,
+        heights: &HeightMap,
+        block_size: usize,
+        margin: f32,
+    ) -> bool {
+        let new_x = self.pos.x + step * self.heading.cos();
+        let new_y = self.pos.y + step * self.heading.sin();
+        let z = ground_height(self.pos.x, self.pos.y, heights, margin);
+        let mut moved = false;
+
+        if can_stand(new_x, self.pos.y, z, maze, heights, block_size, margin) {
+            self.pos.x = new_x;
+            moved = true;
+        }
+        if can_stand(self.pos.x, new_y, z, maze, heights, block_size, margin) {
+            self.pos.y = new_y;
+            moved = true;
+        }
+
+        moved
+    }
+}
+
+pub fn load_enemy_types(filename: &str) -> Result<Vec<EnemyType>, String> {
+    let text = fs::read_to_string(filename)
+        .map_err(|e| format!("Can't read enemy types from {}: {}", filename, e))?;
+
+    let mut enemy_types = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        // Skip blank lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // key name sprite speed damage health detection_radius behaviour
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if fields.len() != 8 || fields[0].chars().count() != 1 {
+            println!("Skipping malformed enemy type: {}", line);
+            continue;
+        }
+
+        let key = fields[0].chars().next().unwrap();
+        if RESERVED_KEYS.contains(&key) || ThinWall::from_char(key).is_some() {
+            println!(
+                "Skipping enemy type {} - '{}' is already used by levels",
+                fields[1], key
+            );
+            continue;
+        }
+
+        let Some(behaviour) = Behaviour::from_name(fields[7]) else {
+            println!("Unknown enemy behaviour '{}' for {}", fields[7], fields[1]);
+            continue;
+        };
+
+        enemy_types.push(EnemyType {
+            key,
+            name: fields[1].to_string(),
+            sprite: fields[2].to_string(),
+            speed: fields[3].parse().unwrap_or(0.0),
+            damage: fields[4].parse().unwrap_or(1),
+            health: fields[5].parse().unwrap_or(1),
+            detection_radius: fields[6].parse().unwrap_or(0.0),
+            behaviour,
+        });
+    }
+
+    Ok(enemy_types)
 }
@@ -0,0 +1,93 @@
+use raylib::prelude::*;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FogMode {
+    Linear,      // Fades in evenly between `start` and `end`
+    Exponential, // Thickens with `density` past `start`
+}
+
+// Distance fog blended over walls, floors, the sky and sprites
+#[derive(Clone, Copy)]
+pub struct Fog {
+    pub mode: FogMode,
+    pub color: Color,
+    pub start: f32,
+    pub end: f32,
+    pub density: f32,
+    pub max: f32, // Strongest the fog gets, 1.0 hides things completely
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fog {
+    // Levels without an `@fog` line darken toward black but never fade out
+    pub fn new() -> Self {
+        Fog {
+            mode: FogMode::Linear,
+            color: Color::BLACK,
+            start: 0.0,
+            end: 1500.0,
+            density: 0.0,
+            max: 0.7,
+        }
+    }
+
+    pub fn linear(color: Color, start: f32, end: f32) -> Self {
+        Fog {
+            mode: FogMode::Linear,
+            color,
+            start,
+            end: end.max(start + 1.0),
+            density: 0.0,
+            max: 1.0,
+        }
+    }
+
+    pub fn exponential(color: Color, start: f32, density: f32) -> Self {
+        Fog {
+            mode: FogMode::Exponential,
+            color,
+            start,
+            end: f32::MAX,
+            density: density.max(0.0),
+            max: 1.0,
+        }
+    }
+
+    // How much of the fog colour shows at a distance, 0.0 (none) to `max`
+    pub fn amount(&self, distance: f32) -> f32 {
+        let depth = (distance - self.start).max(0.0);
+        let amount = match self.mode {
+            FogMode::Linear => depth / (self.end - self.start),
+            FogMode::Exponential => 1.0 - (-self.density * depth).exp(),
+        };
+        amount.clamp(0.0, self.max)
+    }
+
+    // Anything further away than this is lost in the fog and needn't be drawn
+    pub fn cull_distance(&self) -> f32 {
+        if self.max < 1.0 {
+            return f32::MAX;
+        }
+        match self.mode {
+            FogMode::Linear => self.end,
+            FogMode::Exponential if self.density > 0.0 => self.start + 100.0f32.ln() / self.density,
+            FogMode::Exponential => f32::MAX,
+        }
+    }
+
+    pub fn apply(&self, color: Color, distance: f32) -> Color {
+        let amount = self.amount(distance);
+        let blend = |from: u8, to: u8| (from as f32 + (to as f32 - from as f32) * amount) as u8;
+        Color::new(
+            blend(color.r, self.color.r),
+            blend(color.g, self.color.g),
+            blend(color.b, self.color.b),
+            color.a,
+        )
+    }
+}
@@ -0,0 +1,134 @@
+use crate::canvas::{Canvas, blend};
+use raylib::prelude::*;
+use rayon::prelude::*;
+use std::ops::Range;
+
+// Rays cast across the view unless the player asks for more
+pub const DEFAULT_RAYS: usize = 320;
+
+// The 3D view, drawn in memory so every ray can be cast and shaded on its own
+// core. Pixels are kept a column at a time, so the columns belonging to one
+// ray form a single slice that no other ray touches.
+pub struct Framebuffer {
+    rays: usize,
+    width: usize,
+    height: usize,
+    columns: Vec<Color>, // Column by column, as the rays fill them
+    rows: Vec<Color>,    // The same pixels row by row, for copying to the screen
+}
+
+// The screen columns one ray paints
+pub struct Strip<'a> {
+    columns: Range<i32>,
+    height: i32,
+    pixels: &'a mut [Color],
+}
+
+impl Framebuffer {
+    pub fn new(rays: usize) -> Self {
+        Framebuffer {
+            rays,
+            width: 0,
+            height: 0,
+            columns: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn rays(&self) -> usize {
+        self.rays
+    }
+
+    pub fn set_rays(&mut self, rays: usize) {
+        self.rays = rays;
+    }
+
+    // Rays actually cast across a view this wide: never more than one per column
+    pub fn ray_count(&self, width: i32) -> usize {
+        self.rays.clamp(1, width.max(1) as usize)
+    }
+
+    // Clear the frame to black and paint one strip per ray across all cores.
+    // Whatever each ray returns comes back in ray order.
+    pub fn paint<R, F>(&mut self, width: i32, height: i32, paint_ray: F) -> Vec<R>
+    where
+        R: Send,
+        F: Fn(usize, &mut Strip) -> R + Sync,
+    {
+        let (width, height) = (width.max(0) as usize, height.max(0) as usize);
+        self.width = width;
+        self.height = height;
+        self.columns.clear();
+        self.columns.resize(width * height, Color::BLACK);
+
+        // The ray columns split the screen exactly, the spare pixels spread
+        // evenly between them
+        let num_rays = self.ray_count(width as i32);
+        let mut strips = Vec::with_capacity(num_rays);
+        let mut rest = self.columns.as_mut_slice();
+        for i in 0..num_rays {
+            let x = i * width / num_rays;
+            let x_end = (i + 1) * width / num_rays;
+            let (pixels, tail) = rest.split_at_mut((x_end - x) * height);
+            rest = tail;
+            strips.push(Strip {
+                columns: x as i32..x_end as i32,
+                height: height as i32,
+                pixels,
+            });
+        }
+
+        strips
+            .into_par_iter()
+            .enumerate()
+            .map(|(i, mut strip)| paint_ray(i, &mut strip))
+            .collect()
+    }
+
+    // Copy the finished frame to the screen
+    pub fn draw(&mut self, d: &mut dyn Canvas) {
+        let (width, height) = (self.width, self.height);
+        if width == 0 || height == 0 {
+            return;
+        }
+
+        self.rows.resize(width * height, Color::BLACK);
+        let columns = &self.columns;
+        self.rows
+            .par_chunks_mut(width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, pixel) in row.iter_mut().enumerate() {
+                    *pixel = columns[x * height + y];
+                }
+            });
+        d.draw_pixels(0, 0, width, &self.rows);
+    }
+}
+
+impl Strip<'_> {
+    // Screen columns covered by this ray
+    pub fn columns(&self) -> Range<i32> {
+        self.columns.clone()
+    }
+
+    // Fill some rows of every column in the strip, mixing in translucent colours
+    pub fn fill(&mut self, rows: Range<i32>, color: Color) {
+        let start = rows.start.clamp(0, self.height) as usize;
+        let end = rows.end.clamp(0, self.height) as usize;
+        if end <= start {
+            return;
+        }
+
+        for column in self.pixels.chunks_mut(self.height as usize) {
+            let pixels = &mut column[start..end];
+            if color.a == 255 {
+                pixels.fill(color);
+            } else {
+                for pixel in pixels {
+                    *pixel = blend(*pixel, color);
+                }
+            }
+        }
+    }
+}
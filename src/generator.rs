@@ -0,0 +1,112 @@
+// Random mazes in the same text format as the level files. The same seed
+// always gives the same maze, so a generated level can be shared or
+// replayed by its seed alone.
+
+// Small xorshift generator; plenty for laying out mazes
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        // Xorshift gets stuck on zero, and nearby seeds should still differ
+        Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+}
+
+// A perfect maze of `cols` x `rows` rooms, each two blocks wide like the
+// shipped levels. The player starts in the top-left room and the finish is
+// in the bottom-right one, with a checkpoint and a few enemies in between.
+pub fn generate_maze(cols: usize, rows: usize, seed: u64) -> String {
+    let (cols, rows) = (cols.max(2), rows.max(2));
+    let mut rng = Rng::new(seed);
+
+    // Start with every wall up, then knock walls down along a random walk
+    let mut grid: Vec<Vec<char>> = (0..rows * 2 + 1)
+        .map(|line| {
+            (0..cols * 3 + 1)
+                .map(|x| match (line % 2 == 0, x % 3 == 0) {
+                    (true, true) => '+',
+                    (true, false) => '-',
+                    (false, true) => '|',
+                    (false, false) => ' ',
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut visited = vec![vec![false; cols]; rows];
+    let mut stack: Vec<(usize, usize)> = vec![(0, 0)];
+    visited[0][0] = true;
+    while let Some(&(col, row)) = stack.last() {
+        let neighbours: Vec<(usize, usize)> = [
+            (col.wrapping_sub(1), row),
+            (col + 1, row),
+            (col, row.wrapping_sub(1)),
+            (col, row + 1),
+        ]
+        .into_iter()
+        .filter(|&(c, r)| c < cols && r < rows && !visited[r][c])
+        .collect();
+
+        if neighbours.is_empty() {
+            stack.pop();
+            continue;
+        }
+
+        let (next_col, next_row) = neighbours[rng.below(neighbours.len())];
+        if next_row == row {
+            // Wall between rooms side by side
+            grid[row * 2 + 1][col.max(next_col) * 3] = ' ';
+        } else {
+            // Wall between rooms one above the other
+            let line = row.max(next_row) * 2;
+            grid[line][col * 3 + 1] = ' ';
+            grid[line][col * 3 + 2] = ' ';
+        }
+        visited[next_row][next_col] = true;
+        stack.push((next_col, next_row));
+    }
+
+    // Anything placed goes in the left half of a room, away from the start
+    // and the finish
+    let place = |grid: &mut Vec<Vec<char>>, (col, row): (usize, usize), ch: char| {
+        grid[row * 2 + 1][col * 3 + 1] = ch;
+    };
+    place(&mut grid, (cols - 1, rows - 1), 'w');
+    let rooms = cols * rows;
+    let mut free: Vec<(usize, usize)> = (0..rooms)
+        .map(|i| (i % cols, i / cols))
+        .filter(|&(col, row)| col + row > 1 && (col, row) != (cols - 1, rows - 1))
+        .collect();
+    let mut take = |rng: &mut Rng| {
+        (!free.is_empty()).then(|| {
+            let i = rng.below(free.len());
+            free.swap_remove(i)
+        })
+    };
+    if let Some(room) = take(&mut rng) {
+        place(&mut grid, room, 'c');
+    }
+    for _ in 0..rooms / 12 {
+        if let Some(room) = take(&mut rng) {
+            place(&mut grid, room, 'e');
+        }
+    }
+
+    let mut text = String::new();
+    for line in grid {
+        text.extend(line);
+        text.push('\n');
+    }
+    text
+}
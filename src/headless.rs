@@ -0,0 +1,72 @@
+use crate::canvas::SoftwareCanvas;
+use crate::playing::render_world;
+use crate::viewport::Viewport;
+use crate::wall_textures::WallTextures;
+use crate::world::World;
+use std::f32::consts::PI;
+use std::time::{Duration, Instant};
+
+// Frame times from drawing a level without a window
+pub struct BenchReport {
+    pub frames: usize,
+    pub total: Duration,
+    pub slowest: Duration,
+}
+
+impl BenchReport {
+    pub fn average_ms(&self) -> f64 {
+        self.total.as_secs_f64() * 1000.0 / self.frames.max(1) as f64
+    }
+
+    pub fn slowest_ms(&self) -> f64 {
+        self.slowest.as_secs_f64() * 1000.0
+    }
+
+    pub fn fps(&self) -> f64 {
+        1000.0 / self.average_ms()
+    }
+}
+
+// Draw one frame of the world as it stands and save it as an image
+pub fn render_frame(
+    world: &World,
+    wall_textures: &WallTextures,
+    viewport: &mut Viewport,
+    width: i32,
+    height: i32,
+    path: &str,
+) -> Result<(), String> {
+    let mut canvas = SoftwareCanvas::new(width, height);
+    render_world(&mut canvas, world, wall_textures, viewport, width, height);
+    canvas.export(path)
+}
+
+// Draw `frames` frames while turning the player once around on the spot,
+// so every direction of the level is seen
+pub fn benchmark(
+    world: &mut World,
+    wall_textures: &WallTextures,
+    viewport: &mut Viewport,
+    width: i32,
+    height: i32,
+    frames: usize,
+) -> BenchReport {
+    let mut canvas = SoftwareCanvas::new(width, height);
+    let start_angle = world.player.a;
+    let mut report = BenchReport {
+        frames,
+        total: Duration::ZERO,
+        slowest: Duration::ZERO,
+    };
+
+    for frame in 0..frames {
+        world.player.a = start_angle + 2.0 * PI * frame as f32 / frames as f32;
+        let start = Instant::now();
+        render_world(&mut canvas, world, wall_textures, viewport, width, height);
+        let elapsed = start.elapsed();
+        report.total += elapsed;
+        report.slowest = report.slowest.max(elapsed);
+    }
+    world.player.a = start_angle;
+    report
+}
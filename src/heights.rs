@@ -0,0 +1,71 @@
+use raylib::prelude::*;
+
+// Ceiling height of '_' cells, too low to stand up in
+pub const LOW_CEILING_HEIGHT: f32 = 45.0;
+
+// Heights of every cell in the maze. Walls are solid from the floor up to
+// their top; open cells have a floor at their top, which is 0 except on
+// raised platforms.
+pub struct HeightMap {
+    tops: Vec<Vec<f32>>,
+    ceilings: Vec<Vec<f32>>, // f32::MAX where nothing is overhead
+    block_size: f32,
+    max_top: f32,
+}
+
+impl HeightMap {
+    pub fn new(tops: Vec<Vec<f32>>, ceilings: Vec<Vec<f32>>, block_size: f32) -> Self {
+        let max_top = tops.iter().flatten().copied().fold(block_size, f32::max);
+        HeightMap {
+            tops,
+            ceilings,
+            block_size,
+            max_top,
+        }
+    }
+
+    // Top of the cell at column i, row j. Outside the maze counts as a wall
+    // taller than anything in it.
+    pub fn top(&self, i: usize, j: usize) -> f32 {
+        self.tops
+            .get(j)
+            .and_then(|row| row.get(i))
+            .copied()
+            .unwrap_or(self.max_top)
+    }
+
+    pub fn top_at(&self, pos: Vector2) -> f32 {
+        if pos.x < 0.0 || pos.y < 0.0 {
+            return self.max_top;
+        }
+        self.top(
+            (pos.x / self.block_size) as usize,
+            (pos.y / self.block_size) as usize,
+        )
+    }
+
+    pub fn ceiling_at(&self, pos: Vector2) -> f32 {
+        if pos.x < 0.0 || pos.y < 0.0 {
+            return f32::MAX;
+        }
+        let i = (pos.x / self.block_size) as usize;
+        let j = (pos.y / self.block_size) as usize;
+        self.ceilings
+            .get(j)
+            .and_then(|row| row.get(i))
+            .copied()
+            .unwrap_or(f32::MAX)
+    }
+
+    pub fn has_low_ceilings(&self) -> bool {
+        self.ceilings
+            .iter()
+            .flatten()
+            .any(|&ceiling| ceiling < f32::MAX)
+    }
+
+    // Nothing in the level is taller than this, so rays can stop at it
+    pub fn max_top(&self) -> f32 {
+        self.max_top
+    }
+}
@@ -0,0 +1,320 @@
+use crate::canvas::Canvas;
+use crate::player::Player;
+use crate::thin_walls::ThinWall;
+use crate::viewport::Viewport;
+use crate::weapon::Weapon;
+use crate::world::World;
+use raylib::prelude::*;
+
+// The HUD and menus are laid out for a 1300x900 window, and shrink or grow
+// with the window from there
+pub fn ui_scale(window_width: i32, window_height: i32) -> f32 {
+    (window_width as f32 / 1300.0).min(window_height as f32 / 900.0)
+}
+
+// A size from the 1300x900 layout at the current scale, never below a pixel
+pub fn scaled(size: i32, scale: f32) -> i32 {
+    ((size as f32 * scale).round() as i32).max(1)
+}
+
+pub fn render_lives(d: &mut dyn Canvas, lives: i32, window_width: i32, window_height: i32) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    let circle_radius = px(15) as f32;
+    let circle_spacing = px(40);
+    let y = window_height - px(50);
+
+    // Draw "Lives:" text
+    let text = "Lives:";
+    let text_size = px(25);
+    let text_width = d.measure_text(text, text_size);
+
+    // Calculate positions
+    let gap = px(20); // Between text and circles
+    let total_circles_width = (2 * circle_spacing) - (circle_spacing - circle_radius as i32 * 2);
+    let total_width = text_width + gap + total_circles_width;
+    let start_x = (window_width - total_width) / 2;
+
+    // Draw text
+    d.draw_text(text, start_x, y - px(7), text_size, Color::WHITE);
+
+    // Draw circles
+    let circles_start_x = start_x + text_width + gap;
+    for i in 0..2 {
+        let x = circles_start_x + (i * circle_spacing) + circle_radius as i32;
+        if i < lives {
+            d.draw_circle(x, y, circle_radius, Color::RED);
+        } else {
+            d.draw_circle_lines(x, y, circle_radius, Color::new(100, 0, 0, 255));
+        }
+    }
+}
+
+pub fn render_weapon(
+    d: &mut dyn Canvas,
+    player: &Player,
+    weapon: &Weapon,
+    window_width: i32,
+    window_height: i32,
+) {
+    let scale = ui_scale(window_width, window_height);
+    let px = |size| scaled(size, scale);
+
+    // Shots fly level with the horizon, so the crosshair follows it
+    let horizon = player.horizon(window_height as f32);
+    let center_x = window_width / 2;
+    let center = Vector2::new(center_x as f32, horizon.trunc());
+    let arm = px(8) as f32;
+    d.draw_line(
+        center - Vector2::new(arm, 0.0),
+        center + Vector2::new(arm, 0.0),
+        1.0,
+        Color::WHITE,
+    );
+    d.draw_line(
+        center - Vector2::new(0.0, arm),
+        center + Vector2::new(0.0, arm),
+        1.0,
+        Color::WHITE,
+    );
+
+    // Kick the gun down a little right after a shot, and let it sway a
+    // little with the view when looking up or down
+    let recoil = if weapon.flash_timer > 0.0 { px(20) } else { 0 };
+    let sway = ((horizon - window_height as f32 / 2.0) * 0.15) as i32;
+    let base_y = window_height - px(170) + recoil + sway;
+
+    // Muzzle flash
+    if weapon.flash_timer > 0.0 {
+        let flash_y = base_y - px(15);
+        d.draw_circle(
+            center_x,
+            flash_y,
+            28.0 * scale,
+            Color::new(255, 200, 60, 220),
+        );
+        d.draw_circle(
+            center_x,
+            flash_y,
+            14.0 * scale,
+            Color::new(255, 250, 200, 255),
+        );
+    }
+
+    // Barrel, body and grip
+    d.draw_rectangle(
+        center_x - px(12),
+        base_y,
+        px(24),
+        px(80),
+        Color::new(60, 60, 70, 255),
+    );
+    d.draw_rectangle(
+        center_x - px(40),
+        base_y + px(70),
+        px(80),
+        px(60),
+        Color::new(45, 45, 55, 255),
+    );
+    d.draw_rectangle(
+        center_x - px(22),
+        base_y + px(120),
+        px(44),
+        px(60),
+        Color::new(80, 55, 35, 255),
+    );
+    d.draw_rectangle(
+        center_x - px(6),
+        base_y,
+        px(12),
+        px(6),
+        Color::new(20, 20, 25, 255),
+    );
+}
+
+pub fn render_ammo(d: &mut dyn Canvas, ammo: i32, window_width: i32, window_height: i32) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    let text = format!("Ammo: {}", ammo);
+    let text_size = px(25);
+    let text_width = d.measure_text(&text, text_size);
+    let color = if ammo > 0 { Color::WHITE } else { Color::RED };
+
+    d.draw_text(
+        &text,
+        window_width - text_width - px(30),
+        window_height - px(57),
+        text_size,
+        color,
+    );
+}
+
+pub fn render_minimap(d: &mut dyn Canvas, world: &World, window_width: i32, window_height: i32) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    let maze = &world.maze;
+    let player = &world.player;
+
+    // An eighth of a block at the 1300x900 layout, but at least two pixels
+    let minimap_block_size = px(world.block_size as i32 / 8).max(2);
+    let to_map =
+        |distance: f32| (distance * minimap_block_size as f32 / world.block_size as f32) as i32;
+    let minimap_width = (maze[0].len() as i32) * minimap_block_size;
+    let minimap_height = (maze.len() as i32) * minimap_block_size;
+    let margin = px(20);
+
+    let minimap_x = window_width - minimap_width - margin;
+    let minimap_y = margin;
+    let dot_radius = px(3) as f32;
+
+    // Draw minimap background
+    d.draw_rectangle(
+        minimap_x - 2,
+        minimap_y - 2,
+        minimap_width + 4,
+        minimap_height + 4,
+        Color::new(0, 0, 0, 180),
+    );
+
+    // Draw maze walls, with thin walls as a line through their cell
+    for (row_index, row) in maze.iter().enumerate() {
+        for (col_index, &cell) in row.iter().enumerate() {
+            let x = minimap_x + (col_index as i32 * minimap_block_size);
+            let y = minimap_y + (row_index as i32 * minimap_block_size);
+            if let Some(wall) = ThinWall::from_char(cell) {
+                let (start, end) = wall.endpoints(0, 0, minimap_block_size as f32);
+                d.draw_line(
+                    Vector2::new(x as f32, y as f32) + start,
+                    Vector2::new(x as f32, y as f32) + end,
+                    2.0,
+                    Color::GRAY,
+                );
+            } else if cell != ' ' {
+                d.draw_rectangle(x, y, minimap_block_size, minimap_block_size, Color::GRAY);
+            }
+        }
+    }
+
+    // Draw portal links as a line between the two linked cells
+    let cell_center = |(i, j): (usize, usize)| {
+        Vector2::new(
+            (minimap_x + i as i32 * minimap_block_size + minimap_block_size / 2) as f32,
+            (minimap_y + j as i32 * minimap_block_size + minimap_block_size / 2) as f32,
+        )
+    };
+    for (from, to) in world.portals.pairs() {
+        let portal_color = Color::new(150, 90, 255, 255);
+        d.draw_line(
+            cell_center(from),
+            cell_center(to),
+            1.0,
+            portal_color.alpha(0.5),
+        );
+        for end in [cell_center(from), cell_center(to)] {
+            d.draw_circle(end.x as i32, end.y as i32, dot_radius, portal_color);
+        }
+    }
+
+    // Draw checkpoints
+    for checkpoint in &world.checkpoints {
+        let x = minimap_x + to_map(checkpoint.pos.x);
+        let y = minimap_y + to_map(checkpoint.pos.y);
+        let color = if checkpoint.activated {
+            Color::new(40, 220, 120, 255)
+        } else {
+            Color::new(140, 140, 170, 255)
+        };
+        d.draw_circle(x, y, dot_radius, color);
+    }
+
+    // Draw player
+    let player_x = minimap_x + to_map(player.pos.x);
+    let player_y = minimap_y + to_map(player.pos.y);
+    d.draw_circle(player_x, player_y, dot_radius, Color::GREEN);
+
+    // Draw direction
+    let pointer = px(15) as f32;
+    let dir_x = player_x + (pointer * player.a.cos()) as i32;
+    let dir_y = player_y + (pointer * player.a.sin()) as i32;
+    d.draw_line(
+        Vector2::new(player_x as f32, player_y as f32),
+        Vector2::new(dir_x as f32, dir_y as f32),
+        1.0,
+        Color::RED,
+    );
+}
+
+// Numbers for working on levels and the renderer, under the FPS counter
+pub fn render_debug(
+    d: &mut dyn Canvas,
+    world: &World,
+    viewport: &Viewport,
+    window_width: i32,
+    window_height: i32,
+) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    let player = &world.player;
+    let (view_width, view_height) = viewport.size(window_width, window_height);
+    let lines = [
+        format!(
+            "Pos: {:.0}, {:.0} (cell {}, {})",
+            player.pos.x,
+            player.pos.y,
+            player.pos.x as usize / world.block_size,
+            player.pos.y as usize / world.block_size
+        ),
+        format!(
+            "Angle: {:.0} deg  FOV: {:.0} deg",
+            player.a.to_degrees().rem_euclid(360.0),
+            player.fov.to_degrees()
+        ),
+        format!("Eye height: {:.0}", player.eye_height()),
+        format!(
+            "View: {}x{} in {}x{}, {} rays",
+            view_width,
+            view_height,
+            window_width,
+            window_height,
+            viewport.view.framebuffer.ray_count(view_width)
+        ),
+        format!(
+            "Enemies: {} alive of {}",
+            world
+                .enemies
+                .iter()
+                .filter(|enemy| enemy.is_alive())
+                .count(),
+            world.enemies.len()
+        ),
+        format!(
+            "Projectiles: {}",
+            world
+                .projectiles
+                .projectiles
+                .iter()
+                .filter(|projectile| projectile.active)
+                .count()
+        ),
+    ];
+
+    let font_size = px(16);
+    let line_height = px(20);
+    let width = lines
+        .iter()
+        .map(|line| d.measure_text(line, font_size))
+        .max()
+        .unwrap_or(0);
+    d.draw_rectangle(
+        px(5),
+        px(60),
+        width + px(10),
+        lines.len() as i32 * line_height + px(6),
+        Color::new(0, 0, 0, 160),
+    );
+    for (i, line) in lines.iter().enumerate() {
+        d.draw_text(
+            line,
+            px(10),
+            px(64) + i as i32 * line_height,
+            font_size,
+            Color::YELLOW,
+        );
+    }
+}
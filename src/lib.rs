@@ -0,0 +1,53 @@
+// The game's levels, simulation, renderers and screens. The `raycaster`
+// binary reads its command line, then either opens a window and runs the
+// scene stack or does one of its jobs without a window.
+pub mod animation;
+pub mod assets;
+pub mod audio;
+pub mod canvas;
+pub mod caster;
+pub mod checkpoint;
+pub mod depth;
+pub mod enemy;
+pub mod fog;
+pub mod framebuffer;
+pub mod generator;
+pub mod headless;
+pub mod heights;
+pub mod hud;
+pub mod lighting;
+pub mod maze;
+pub mod panes;
+pub mod pickup;
+pub mod player;
+pub mod playing;
+pub mod portals;
+pub mod projectile;
+pub mod render;
+pub mod replay;
+pub mod scene;
+pub mod screens;
+pub mod sky;
+pub mod thin_walls;
+pub mod viewport;
+pub mod wall_textures;
+pub mod weapon;
+pub mod world;
+
+pub use assets::{Assets, Picture};
+pub use canvas::{Canvas, RaylibCanvas, Scaling, SoftwareCanvas, scale_pixels};
+pub use caster::cast_ray;
+pub use enemy::{Enemy, EnemyType, load_enemy_types};
+pub use framebuffer::Framebuffer;
+pub use hud::{render_ammo, render_debug, render_lives, render_minimap, render_weapon};
+pub use maze::{Level, LevelSource, Maze, load_maze, parse_maze};
+pub use player::{Controls, Player};
+pub use render::{render_sprites, render3d};
+pub use replay::Replay;
+pub use scene::{Context, Scene, SceneStack, Transition};
+pub use screens::{
+    render_game_over, render_level_select, render_menu, render_pause, render_victory,
+};
+pub use viewport::{View, Viewport};
+pub use wall_textures::WallTextures;
+pub use world::{Event, World};
@@ -0,0 +1,187 @@
+use crate::maze::{Maze, has_line_of_sight};
+use raylib::prelude::*;
+
+#[derive(Clone)]
+pub struct Light {
+    pub pos: Vector2,
+    pub radius: f32,
+    pub color: Color,
+    pub flicker: bool,
+}
+
+impl Light {
+    pub fn new(x: f32, y: f32, radius: f32, color: Color, flicker: bool) -> Self {
+        Light {
+            pos: Vector2::new(x, y),
+            radius,
+            color,
+            flicker,
+        }
+    }
+
+    // Brightness multiplier for flickering lights, wobbling around 0.85. Each
+    // light gets its own phase from its position so torches don't pulse in step.
+    fn flicker_at(&self, time: f32) -> f32 {
+        if !self.flicker {
+            return 1.0;
+        }
+        let phase = self.pos.x * 0.013 + self.pos.y * 0.029;
+        0.85 + 0.15 * (time * 13.0 + phase).sin() * (time * 7.3 + phase * 2.0).sin()
+    }
+}
+
+// Per-cell light levels. How much of each light reaches each cell is worked
+// out once when the level loads (walls block light); flicker is applied on
+// top every frame in `update`.
+pub struct LightMap {
+    lights: Vec<Light>,
+    ambient: f32,
+    width: usize,
+    height: usize,
+    block_size: f32,
+    contributions: Vec<Vec<f32>>, // One intensity per cell for each light
+    cells: Vec<[f32; 3]>,         // Current RGB light per cell, 1.0 = full brightness
+}
+
+impl LightMap {
+    pub fn new(maze: &Maze, lights: Vec<Light>, ambient: f32, block_size: usize) -> Self {
+        let height = maze.len();
+        let width = maze.first().map_or(0, |row| row.len());
+        let cell_size = block_size as f32;
+
+        let is_open = |i: usize, j: usize| maze[j].get(i) == Some(&' ');
+
+        let mut contributions = Vec::with_capacity(lights.len());
+        for light in &lights {
+            let mut intensity = vec![0.0; width * height];
+
+            // Open cells are lit when the light can see their centre
+            for j in 0..height {
+                for i in 0..width {
+                    if !is_open(i, j) {
+                        continue;
+                    }
+                    let center = Vector2::new(
+                        i as f32 * cell_size + cell_size / 2.0,
+                        j as f32 * cell_size + cell_size / 2.0,
+                    );
+                    let dx = center.x - light.pos.x;
+                    let dy = center.y - light.pos.y;
+                    let distance = (dx * dx + dy * dy).sqrt();
+                    if distance >= light.radius
+                        || !has_line_of_sight(maze, light.pos, center, block_size)
+                    {
+                        continue;
+                    }
+                    let falloff = 1.0 - distance / light.radius;
+                    intensity[j * width + i] = falloff * falloff;
+                }
+            }
+
+            // Wall cells take the brightest open neighbour so wall faces and
+            // the smoothing between cells pick up the light next to them
+            let open_intensity = intensity.clone();
+            for j in 0..height {
+                for i in 0..width {
+                    if is_open(i, j) {
+                        continue;
+                    }
+                    let mut brightest: f32 = 0.0;
+                    for (ni, nj) in [
+                        (i.wrapping_sub(1), j),
+                        (i + 1, j),
+                        (i, j.wrapping_sub(1)),
+                        (i, j + 1),
+                    ] {
+                        if nj < height && ni < width && is_open(ni, nj) {
+                            brightest = brightest.max(open_intensity[nj * width + ni]);
+                        }
+                    }
+                    intensity[j * width + i] = brightest;
+                }
+            }
+
+            contributions.push(intensity);
+        }
+
+        let mut light_map = LightMap {
+            lights,
+            ambient,
+            width,
+            height,
+            block_size: cell_size,
+            contributions,
+            cells: vec![[ambient; 3]; width * height],
+        };
+        light_map.update(0.0);
+        light_map
+    }
+
+    // Levels without lights keep full brightness and skip all light work
+    pub fn is_lit(&self) -> bool {
+        !self.lights.is_empty() || self.ambient < 1.0
+    }
+
+    // Recombine the baked contributions with this frame's flicker
+    pub fn update(&mut self, time: f32) {
+        if !self.is_lit() {
+            return;
+        }
+
+        for cell in self.cells.iter_mut() {
+            *cell = [self.ambient; 3];
+        }
+
+        for (light, intensity) in self.lights.iter().zip(&self.contributions) {
+            let strength = light.flicker_at(time);
+            let r = light.color.r as f32 / 255.0 * strength;
+            let g = light.color.g as f32 / 255.0 * strength;
+            let b = light.color.b as f32 / 255.0 * strength;
+
+            for (cell, &amount) in self.cells.iter_mut().zip(intensity) {
+                if amount > 0.0 {
+                    cell[0] += r * amount;
+                    cell[1] += g * amount;
+                    cell[2] += b * amount;
+                }
+            }
+        }
+    }
+
+    // Light at a world position, blended between the four nearest cell centres
+    pub fn sample(&self, pos: Vector2) -> [f32; 3] {
+        if !self.is_lit() || self.width == 0 || self.height == 0 {
+            return [1.0; 3];
+        }
+
+        let fx = (pos.x / self.block_size - 0.5).clamp(0.0, (self.width - 1) as f32);
+        let fy = (pos.y / self.block_size - 0.5).clamp(0.0, (self.height - 1) as f32);
+        let i = fx as usize;
+        let j = fy as usize;
+        let i1 = (i + 1).min(self.width - 1);
+        let j1 = (j + 1).min(self.height - 1);
+        let tx = fx - i as f32;
+        let ty = fy - j as f32;
+
+        let cell = |i: usize, j: usize| self.cells[j * self.width + i];
+        let (c00, c10, c01, c11) = (cell(i, j), cell(i1, j), cell(i, j1), cell(i1, j1));
+
+        let mut light = [0.0; 3];
+        for k in 0..3 {
+            let top = c00[k] + (c10[k] - c00[k]) * tx;
+            let bottom = c01[k] + (c11[k] - c01[k]) * tx;
+            light[k] = top + (bottom - top) * ty;
+        }
+        light
+    }
+}
+
+// Scale a colour by a light level, saturating at full brightness
+pub fn apply_light(color: Color, light: [f32; 3]) -> Color {
+    Color::new(
+        (color.r as f32 * light[0]).min(255.0) as u8,
+        (color.g as f32 * light[1]).min(255.0) as u8,
+        (color.b as f32 * light[2]).min(255.0) as u8,
+        color.a,
+    )
+}
@@ -1,29 +1,235 @@
-use crate::enemy::Enemy;
+use crate::assets::Assets;
+use crate::checkpoint::Checkpoint;
+use crate::enemy::{Enemy, EnemyType};
+use crate::fog::Fog;
+use crate::generator::generate_maze;
+use crate::heights::{HeightMap, LOW_CEILING_HEIGHT};
+use crate::lighting::Light;
+use crate::panes::{Pane, Panes};
+use crate::pickup::Pickup;
+use crate::portals::Portals;
+use crate::thin_walls::{ThinWall, crosses_thin_wall};
 use raylib::prelude::*;
-use std::fs::File;
-use std::io::{BufRead, BufReader};
+use std::fmt;
+use std::fs;
+use std::str::FromStr;
 
 pub type Maze = Vec<Vec<char>>;
 
-pub fn load_maze(filename: &str) -> (Maze, Vec<Enemy>, Option<Vector2>) {
-    let file = File::open(filename).unwrap();
-    let reader = BufReader::new(file);
+pub struct Level {
+    pub maze: Maze,
+    pub enemies: Vec<Enemy>,
+    pub finish_pos: Option<Vector2>,
+    pub checkpoints: Vec<Checkpoint>,
+    pub ammo_pickups: Vec<Pickup>,
+    pub combat: bool,
+    pub lights: Vec<Light>,
+    pub ambient: f32,
+    pub fog: Fog,
+    pub sky: String,
+    pub outdoor: Vec<Vec<bool>>,
+    pub heights: HeightMap,
+    pub panes: Panes,
+    pub portals: Portals,
+}
+
+// Where a level comes from, so that it can be loaded again the same way
+#[derive(Clone, Debug, PartialEq)]
+pub enum LevelSource {
+    File(String),
+    Generated { cols: usize, rows: usize, seed: u64 },
+}
+
+impl LevelSource {
+    // Level files are looked for wherever `assets` finds its other files
+    pub fn load(&self, enemy_types: &[EnemyType], assets: &Assets) -> Result<Level, String> {
+        match self {
+            LevelSource::File(filename) => load_maze(&assets.path(filename), enemy_types),
+            LevelSource::Generated { cols, rows, seed } => Ok(parse_maze(
+                &self.to_string(),
+                &generate_maze(*cols, *rows, *seed),
+                enemy_types,
+            )),
+        }
+    }
+}
+
+// Written the way it is read back from a replay: the file name, or the size
+// and seed of a generated maze
+impl fmt::Display for LevelSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelSource::File(filename) => write!(f, "{}", filename),
+            LevelSource::Generated { cols, rows, seed } => {
+                write!(f, "generated {}x{} seed {}", cols, rows, seed)
+            }
+        }
+    }
+}
 
+impl FromStr for LevelSource {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let Some(generated) = s.strip_prefix("generated ") else {
+            return Ok(LevelSource::File(s.to_string()));
+        };
+        let parse = || {
+            let (size, seed) = generated.split_once(" seed ")?;
+            let (cols, rows) = size.split_once('x')?;
+            Some(LevelSource::Generated {
+                cols: cols.parse().ok()?,
+                rows: rows.parse().ok()?,
+                seed: seed.parse().ok()?,
+            })
+        };
+        parse().ok_or_else(|| format!("Bad generated level '{}'", s))
+    }
+}
+
+pub fn load_maze(filename: &str, enemy_types: &[EnemyType]) -> Result<Level, String> {
+    let text = fs::read_to_string(filename)
+        .map_err(|e| format!("Can't read level {}: {}", filename, e))?;
+    Ok(parse_maze(filename, &text, enemy_types))
+}
+
+// Build a level from the text of a level file. `name` only appears in
+// warnings about the file.
+pub fn parse_maze(name: &str, text: &str, enemy_types: &[EnemyType]) -> Level {
     let mut maze = Vec::new();
     let mut enemies = Vec::new();
     let mut finish_pos = None;
+    let mut checkpoints = Vec::new();
+    let mut ammo_pickups = Vec::new();
+    let mut combat = false;
+    let mut lights = Vec::new();
+    let mut ambient = 1.0;
+    let mut fog = Fog::new();
+    let mut sky = String::from("assets/sky.png");
+    let mut outdoor = Vec::new();
+    let mut wall_heights = Vec::new(); // Wall characters shorter or taller than a block
+    let mut floor_heights = Vec::new(); // Characters for open floor raised off the ground
+    let mut outdoor_keys = vec!['.']; // Characters with the sky above them
+    let mut tops = Vec::new();
+    let mut ceilings = Vec::new();
+    let mut panes = Panes::new();
+    let mut portals = Portals::new();
     let block_size = 100.0;
 
-    for (row_index, line) in reader.lines().enumerate() {
-        let line = line.unwrap();
+    for line in text.lines() {
+        // Lines starting with '@' are level settings, not maze rows
+        if let Some(setting) = line.strip_prefix('@') {
+            let fields: Vec<&str> = setting.split_whitespace().collect();
+            match fields.as_slice() {
+                ["combat"] => combat = true,
+                ["ambient", level] => ambient = level.parse().unwrap_or(1.0),
+                ["light", col, row, radius, r, g, b, rest @ ..] => {
+                    // Lights sit in the center of the cell at column/row of the maze grid
+                    let (Ok(col), Ok(row)) = (col.parse::<f32>(), row.parse::<f32>()) else {
+                        println!("Bad light setting '@{}' in {}", setting.trim(), name);
+                        continue;
+                    };
+                    let color = Color::new(
+                        r.parse().unwrap_or(255),
+                        g.parse().unwrap_or(255),
+                        b.parse().unwrap_or(255),
+                        255,
+                    );
+                    lights.push(Light::new(
+                        col * block_size + block_size / 2.0,
+                        row * block_size + block_size / 2.0,
+                        radius.parse().unwrap_or(300.0),
+                        color,
+                        rest.contains(&"flicker"),
+                    ));
+                }
+                ["fog", mode @ ("linear" | "exp"), r, g, b, start, amount] => {
+                    // Linear fog ends at `amount`, exponential fog uses it as density
+                    let color = Color::new(
+                        r.parse().unwrap_or(0),
+                        g.parse().unwrap_or(0),
+                        b.parse().unwrap_or(0),
+                        255,
+                    );
+                    let start = start.parse().unwrap_or(0.0);
+                    fog = if *mode == "linear" {
+                        Fog::linear(color, start, amount.parse().unwrap_or(1500.0))
+                    } else {
+                        Fog::exponential(color, start, amount.parse().unwrap_or(0.002))
+                    };
+                }
+                ["sky", path] => sky = path.to_string(),
+                [kind @ ("wall" | "floor"), key, height, rest @ ..] if key.chars().count() == 1 => {
+                    let key = key.chars().next().unwrap();
+                    let height: f32 = height.parse().unwrap_or(0.0);
+                    if rest.contains(&"outdoor") {
+                        outdoor_keys.push(key);
+                    }
+                    if *kind == "wall" {
+                        wall_heights.push((key, height));
+                    } else {
+                        floor_heights.push((key, height));
+                    }
+                }
+                ["glass", key, r, g, b, opacity] if key.chars().count() == 1 => {
+                    let opacity: f32 = opacity.parse().unwrap_or(0.3);
+                    let tint = Color::new(
+                        r.parse().unwrap_or(180),
+                        g.parse().unwrap_or(220),
+                        b.parse().unwrap_or(255),
+                        (opacity.clamp(0.0, 1.0) * 255.0) as u8,
+                    );
+                    panes.add(Pane::glass(key.chars().next().unwrap(), tint));
+                }
+                ["grate", key, path] if key.chars().count() == 1 => {
+                    panes.add(Pane::grate(key.chars().next().unwrap(), path));
+                }
+                ["mirror", key, tint @ ..] if key.chars().count() == 1 => {
+                    // A faint blue sheen unless the level picks a colour
+                    let tint = match tint {
+                        [r, g, b, opacity] => Color::new(
+                            r.parse().unwrap_or(200),
+                            g.parse().unwrap_or(220),
+                            b.parse().unwrap_or(255),
+                            (opacity.parse().unwrap_or(0.2f32).clamp(0.0, 1.0) * 255.0) as u8,
+                        ),
+                        _ => Color::new(200, 220, 255, 50),
+                    };
+                    panes.add(Pane::mirror(key.chars().next().unwrap(), tint));
+                }
+                ["portal", col, row, to_col, to_row, rest @ ..] => {
+                    // Portals link the cells at two column/row positions of the maze grid
+                    let cell = |col: &str, row: &str| Some((col.parse().ok()?, row.parse().ok()?));
+                    let quarter_turns = match rest.first() {
+                        Some(turns) => turns.parse().ok(),
+                        None => Some(0),
+                    };
+                    // A portal to the wrong place is worse than none at all
+                    let (Some(from), Some(to), Some(quarter_turns)) =
+                        (cell(col, row), cell(to_col, to_row), quarter_turns)
+                    else {
+                        println!("Bad portal setting '@{}' in {}", setting.trim(), name);
+                        continue;
+                    };
+                    portals.add(from, to, quarter_turns);
+                }
+                _ => println!("Unknown level setting '@{}' in {}", setting.trim(), name),
+            }
+            continue;
+        }
+
+        let row_index = maze.len();
         let mut row = Vec::new();
+        let mut outdoor_row = Vec::new();
+        let mut top_row = Vec::new();
+        let mut ceiling_row = Vec::new();
 
         for (col_index, ch) in line.chars().enumerate() {
-            if ch == 'e' || ch == 'E' {
+            if let Some(enemy_type) = enemy_types.iter().find(|t| t.key == ch) {
                 // Found an enemy, place it in the center of the cell
                 let x = col_index as f32 * block_size + block_size / 2.0;
                 let y = row_index as f32 * block_size + block_size / 2.0;
-                enemies.push(Enemy::new(x, y));
+                enemies.push(Enemy::new(x, y, enemy_type));
                 row.push(' ');
             } else if ch == 'w' || ch == 'W' {
                 // Found the finish/win position, place it in the center of the cell
@@ -31,12 +237,120 @@ pub fn load_maze(filename: &str) -> (Maze, Vec<Enemy>, Option<Vector2>) {
                 let y = row_index as f32 * block_size + block_size / 2.0;
                 finish_pos = Some(Vector2::new(x, y));
                 row.push(' ');
+            } else if ch == 'c' || ch == 'C' {
+                // Found a checkpoint, place it in the center of the cell
+                let x = col_index as f32 * block_size + block_size / 2.0;
+                let y = row_index as f32 * block_size + block_size / 2.0;
+                checkpoints.push(Checkpoint::new(x, y));
+                row.push(' ');
+            } else if ch == 'a' || ch == 'A' {
+                // Found an ammo pickup, place it in the center of the cell
+                let x = col_index as f32 * block_size + block_size / 2.0;
+                let y = row_index as f32 * block_size + block_size / 2.0;
+                ammo_pickups.push(Pickup::new(x, y));
+                row.push(' ');
+            } else if ch == '.' || ch == '_' {
+                // Open floor under the sky ('.') or under a low ceiling ('_')
+                row.push(' ');
+            } else if floor_heights.iter().any(|&(key, _)| key == ch) {
+                // Raised platform, open to walk on
+                row.push(' ');
             } else {
                 row.push(ch);
             }
+
+            // Walls reach a block up unless the level says otherwise; open
+            // floor sits on the ground unless it is a platform
+            let top = if let Some(&(_, height)) = floor_heights.iter().find(|&&(key, _)| key == ch)
+            {
+                height
+            } else if row[col_index] == ' ' || ThinWall::from_char(ch).is_some() {
+                // Thin walls leave most of their cell open floor
+                0.0
+            } else if let Some(&(_, height)) = wall_heights.iter().find(|&&(key, _)| key == ch) {
+                height
+            } else {
+                block_size
+            };
+            top_row.push(top);
+            ceiling_row.push(if ch == '_' {
+                LOW_CEILING_HEIGHT
+            } else {
+                f32::MAX
+            });
+            outdoor_row.push(outdoor_keys.contains(&ch));
         }
         maze.push(row);
+        outdoor.push(outdoor_row);
+        tops.push(top_row);
+        ceilings.push(ceiling_row);
+    }
+
+    Level {
+        maze,
+        enemies,
+        finish_pos,
+        checkpoints,
+        ammo_pickups,
+        combat,
+        lights,
+        ambient,
+        fog,
+        sky,
+        outdoor,
+        heights: HeightMap::new(tops, ceilings, block_size),
+        panes,
+        portals,
+    }
+}
+
+// Look up a per-cell flag (outdoor, ...) at a world position
+pub fn grid_flag(flags: &[Vec<bool>], pos: Vector2, block_size: usize) -> bool {
+    if pos.x < 0.0 || pos.y < 0.0 {
+        return false;
+    }
+    let i = pos.x as usize / block_size;
+    let j = pos.y as usize / block_size;
+    flags
+        .get(j)
+        .and_then(|row| row.get(i))
+        .copied()
+        .unwrap_or(false)
+}
+
+// Walk the segment between two points and report whether it stays clear of walls
+pub fn has_line_of_sight(maze: &Maze, from: Vector2, to: Vector2, block_size: usize) -> bool {
+    let dx = to.x - from.x;
+    let dy = to.y - from.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+    let steps = ((distance / 10.0).ceil() as usize).max(1);
+
+    let mut previous = from;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32;
+        let x = from.x + dx * t;
+        let y = from.y + dy * t;
+        if x < 0.0 || y < 0.0 {
+            return false;
+        }
+
+        // Rows can be shorter than others, so past the end of one is outside too
+        let i = x as usize / block_size;
+        let j = y as usize / block_size;
+        let Some(&cell) = maze.get(j).and_then(|row| row.get(i)) else {
+            return false;
+        };
+
+        // Thin walls only block the part of their cell the line runs through
+        let current = Vector2::new(x, y);
+        if crosses_thin_wall(maze, previous, current, block_size) {
+            return false;
+        }
+        if cell != ' ' && ThinWall::from_char(cell).is_none() {
+            return false;
+        }
+        previous = current;
     }
 
-    (maze, enemies, finish_pos)
+    true
 }
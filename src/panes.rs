@@ -0,0 +1,140 @@
+use crate::assets::Assets;
+use raylib::prelude::*;
+
+// Resolution pane textures are resampled to, matching wall texture coordinates
+const PANE_TEXTURE_SIZE: usize = 128;
+
+// A wall cell that rays see through: tinted glass, a grate whose
+// transparent texels are holes, or a mirror that shows the reflection
+pub struct Pane {
+    key: char,
+    tint: Color,
+    texture: Vec<Color>, // Empty for plain glass and mirrors
+    reflective: bool,
+    image: Option<String>, // Grate texture, loaded along with the level's other assets
+}
+
+impl Pane {
+    pub fn glass(key: char, tint: Color) -> Self {
+        Pane {
+            key,
+            tint,
+            texture: Vec::new(),
+            reflective: false,
+            image: None,
+        }
+    }
+
+    // The tint washes over everything seen in the mirror
+    pub fn mirror(key: char, tint: Color) -> Self {
+        Pane {
+            key,
+            tint,
+            texture: Vec::new(),
+            reflective: true,
+            image: None,
+        }
+    }
+
+    // Plain bars until its texture is loaded
+    pub fn grate(key: char, path: &str) -> Self {
+        Pane {
+            key,
+            tint: Color::WHITE,
+            texture: Self::bars(),
+            reflective: false,
+            image: Some(path.to_string()),
+        }
+    }
+
+    fn load_texture(&mut self, assets: &Assets) {
+        let Some(path) = &self.image else {
+            return;
+        };
+        let image = assets.image(path);
+        if image.is_placeholder() {
+            println!("No grate texture found at {} - using plain bars", path);
+            return;
+        }
+        println!("Loaded grate texture: {}x{}", image.width, image.height);
+        self.texture = image.region(
+            0,
+            0,
+            image.width,
+            image.height,
+            PANE_TEXTURE_SIZE,
+            PANE_TEXTURE_SIZE,
+        );
+    }
+
+    // Iron bars with a crossbar near the top and bottom
+    fn bars() -> Vec<Color> {
+        const IRON: Color = Color::new(70, 70, 78, 255);
+        let mut texture = vec![Color::BLANK; PANE_TEXTURE_SIZE * PANE_TEXTURE_SIZE];
+        for y in 0..PANE_TEXTURE_SIZE {
+            for x in 0..PANE_TEXTURE_SIZE {
+                if x % 32 < 8 || (y % 112) < 8 {
+                    texture[y * PANE_TEXTURE_SIZE + x] = IRON;
+                }
+            }
+        }
+        texture
+    }
+
+    pub fn tint(&self) -> Color {
+        self.tint
+    }
+
+    // Colour of the pane at texture coordinates (0..128). Holes in a grate
+    // have an alpha of 0.
+    pub fn pixel(&self, x: usize, y: usize) -> Color {
+        if self.texture.is_empty() {
+            return self.tint;
+        }
+        let x = x.min(PANE_TEXTURE_SIZE - 1);
+        let y = y.min(PANE_TEXTURE_SIZE - 1);
+        self.texture[y * PANE_TEXTURE_SIZE + x]
+    }
+}
+
+// Every see-through wall character in a level
+pub struct Panes {
+    panes: Vec<Pane>,
+}
+
+impl Default for Panes {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Panes {
+    pub fn new() -> Self {
+        Panes { panes: Vec::new() }
+    }
+
+    pub fn add(&mut self, pane: Pane) {
+        self.panes.retain(|existing| existing.key != pane.key);
+        self.panes.push(pane);
+    }
+
+    // Load the textures of every grate
+    pub fn load_textures(&mut self, assets: &Assets) {
+        for pane in self.panes.iter_mut() {
+            pane.load_texture(assets);
+        }
+    }
+
+    pub fn get(&self, key: char) -> Option<&Pane> {
+        self.panes.iter().find(|pane| pane.key == key)
+    }
+
+    // Whether rays pass straight through walls of this character
+    pub fn is_see_through(&self, key: char) -> bool {
+        self.get(key).is_some_and(|pane| !pane.reflective)
+    }
+
+    pub fn mirror(&self, key: char) -> Option<&Pane> {
+        self.get(key).filter(|pane| pane.reflective)
+    }
+}
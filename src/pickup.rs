@@ -0,0 +1,17 @@
+use crate::animation::{Animation, Sequence};
+use raylib::prelude::*;
+
+#[derive(Clone)]
+pub struct Pickup {
+    pub pos: Vector2,
+    pub animation: Animation,
+}
+
+impl Pickup {
+    pub fn new(x: f32, y: f32) -> Self {
+        Pickup {
+            pos: Vector2::new(x, y),
+            animation: Animation::new(Sequence::Idle),
+        }
+    }
+}
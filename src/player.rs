@@ -1,21 +1,128 @@
+use crate::heights::HeightMap;
 use crate::maze::Maze;
+use crate::portals::Portals;
+use crate::thin_walls::{ThinWall, clear_of_thin_walls};
 use raylib::prelude::*;
 use std::f32::consts::PI;
 
+// How far the view can tilt, as a fraction of the screen height
+const MAX_PITCH: f32 = 0.4;
+
+// Eye level above the floor when standing, half a wall up
+pub const EYE_HEIGHT: f32 = 50.0;
+const CROUCH_EYE_HEIGHT: f32 = 28.0;
+const HEAD_ROOM: f32 = 8.0; // Top of the head above the eyes
+
+const JUMP_SPEED: f32 = 260.0;
+const GRAVITY: f32 = 900.0;
+const CROUCH_SPEED: f32 = 150.0; // How fast the eyes move when ducking or standing up
+const STEP_HEIGHT: f32 = 24.0; // Tallest ledge the player walks up without jumping
+const COLLISION_MARGIN: f32 = 10.0; // Small margin to prevent getting too close to walls
+
 pub struct Player {
     pub pos: Vector2,
     pub a: f32,
     pub fov: f32,
+    pub pitch: f32,  // Vertical look, positive looks up
+    pub z: f32,      // Height of the feet above the ground floor
+    pub vz: f32,     // Vertical speed while jumping or falling
+    pub height: f32, // Eye height above the feet, lower while crouching
+}
+
+impl Player {
+    pub fn new(x: f32, y: f32) -> Self {
+        Player {
+            pos: Vector2::new(x, y),
+            a: PI / 3.0,
+            fov: PI / 3.0,
+            pitch: 0.0,
+            z: 0.0,
+            vz: 0.0,
+            height: EYE_HEIGHT,
+        }
+    }
+
+    // Height of the camera above the floor
+    pub fn eye_height(&self) -> f32 {
+        self.z + self.height
+    }
+
+    pub fn is_crouching(&self) -> bool {
+        self.height < EYE_HEIGHT - 1.0
+    }
+
+    // Screen row of the horizon. Looking up or down shears the whole view
+    // vertically instead of rotating the camera.
+    pub fn horizon(&self, window_height: f32) -> f32 {
+        window_height / 2.0 + self.pitch * window_height
+    }
+}
+
+// What the player is asking for this frame, read from the keyboard and
+// mouse or filled in by hand when stepping the game without a window
+#[derive(Clone, Copy, Default)]
+pub struct Controls {
+    pub forward: bool,
+    pub back: bool,
+    pub left: bool,
+    pub right: bool,
+    pub crouch: bool,
+    pub jump: bool,
+    pub fire: bool,
+    pub turn: f32, // Mouse movement in pixels, positive turns right
+    pub look: f32, // Mouse movement in pixels, positive looks up
+}
+
+impl Controls {
+    pub fn read(window: &RaylibHandle, invert_y: bool) -> Self {
+        let mouse_delta = window.get_mouse_delta();
+        Controls {
+            forward: window.is_key_down(KeyboardKey::KEY_W),
+            back: window.is_key_down(KeyboardKey::KEY_S),
+            left: window.is_key_down(KeyboardKey::KEY_A),
+            right: window.is_key_down(KeyboardKey::KEY_D),
+            crouch: window.is_key_down(KeyboardKey::KEY_C)
+                || window.is_key_down(KeyboardKey::KEY_LEFT_CONTROL),
+            jump: window.is_key_pressed(KeyboardKey::KEY_SPACE),
+            fire: window.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT)
+                || window.is_key_pressed(KeyboardKey::KEY_F),
+            turn: mouse_delta.x,
+            look: if invert_y {
+                mouse_delta.y
+            } else {
+                -mouse_delta.y
+            },
+        }
+    }
 }
 
-pub fn process_events(window: &RaylibHandle, player: &mut Player, maze: &Maze, block_size: usize) {
-    const MOVE_SPEED: f32 = 10.0;
+pub fn process_events(
+    player: &mut Player,
+    controls: &Controls,
+    maze: &Maze,
+    heights: &HeightMap,
+    portals: &Portals,
+    block_size: usize,
+    dt: f32,
+) {
+    const MOVE_SPEED: f32 = 600.0; // Units per second
     const MOUSE_SENSITIVITY: f32 = 0.003; // Mouse sensitivity for horizontal rotation
-    const COLLISION_MARGIN: f32 = 10.0; // Small margin to prevent getting too close to walls
+    const PITCH_SENSITIVITY: f32 = 0.0015; // Screen heights per pixel of vertical mouse movement
+
+    update_height(player, controls, heights, dt);
+
+    // Crouching slows the player down
+    let move_speed = if player.is_crouching() {
+        MOVE_SPEED * 0.5 * dt
+    } else {
+        MOVE_SPEED * dt
+    };
 
     // Mouse control for horizontal camera rotation
-    let mouse_delta = window.get_mouse_delta();
-    player.a += mouse_delta.x * MOUSE_SENSITIVITY;
+    player.a += controls.turn * MOUSE_SENSITIVITY;
+
+    // Mouse control for looking up and down
+    player.pitch = (player.pitch + controls.look * PITCH_SENSITIVITY).clamp(-MAX_PITCH, MAX_PITCH);
 
     // Keep angle in valid range
     if player.a > 2.0 * PI {
@@ -25,58 +132,173 @@ pub fn process_events(window: &RaylibHandle, player: &mut Player, maze: &Maze, b
     }
 
     // WASD movement
-    if window.is_key_down(KeyboardKey::KEY_W) {
-        let new_x = player.pos.x + MOVE_SPEED * player.a.cos();
-        let new_y = player.pos.y + MOVE_SPEED * player.a.sin();
-
-        // Check if new position would be inside a wall
-        if is_valid_position(new_x, new_y, maze, block_size, COLLISION_MARGIN) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
+    let previous = player.pos;
+
+    // Long frames are split into short moves so the player can't skip
+    // through a wall
+    let steps = (move_speed / COLLISION_MARGIN).ceil().max(1.0);
+    let move_speed = move_speed / steps;
+    for _ in 0..steps as usize {
+        if controls.forward {
+            let new_x = player.pos.x + move_speed * player.a.cos();
+            let new_y = player.pos.y + move_speed * player.a.sin();
+
+            // Check if new position would be inside a wall
+            if can_stand(
+                new_x,
+                new_y,
+                player.z,
+                maze,
+                heights,
+                block_size,
+                COLLISION_MARGIN,
+            ) {
+                player.pos.x = new_x;
+                player.pos.y = new_y;
+            }
         }
-    }
-    if window.is_key_down(KeyboardKey::KEY_S) {
-        let new_x = player.pos.x - MOVE_SPEED * player.a.cos();
-        let new_y = player.pos.y - MOVE_SPEED * player.a.sin();
-
-        // Check if new position would be inside a wall
-        if is_valid_position(new_x, new_y, maze, block_size, COLLISION_MARGIN) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
+        if controls.back {
+            let new_x = player.pos.x - move_speed * player.a.cos();
+            let new_y = player.pos.y - move_speed * player.a.sin();
+
+            // Check if new position would be inside a wall
+            if can_stand(
+                new_x,
+                new_y,
+                player.z,
+                maze,
+                heights,
+                block_size,
+                COLLISION_MARGIN,
+            ) {
+                player.pos.x = new_x;
+                player.pos.y = new_y;
+            }
         }
-    }
-    if window.is_key_down(KeyboardKey::KEY_A) {
-        // Strafe left (perpendicular to viewing direction)
-        let strafe_angle = player.a - PI / 2.0;
-        let new_x = player.pos.x + MOVE_SPEED * strafe_angle.cos();
-        let new_y = player.pos.y + MOVE_SPEED * strafe_angle.sin();
-
-        if is_valid_position(new_x, new_y, maze, block_size, COLLISION_MARGIN) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
+        if controls.left {
+            // Strafe left (perpendicular to viewing direction)
+            let strafe_angle = player.a - PI / 2.0;
+            let new_x = player.pos.x + move_speed * strafe_angle.cos();
+            let new_y = player.pos.y + move_speed * strafe_angle.sin();
+
+            if can_stand(
+                new_x,
+                new_y,
+                player.z,
+                maze,
+                heights,
+                block_size,
+                COLLISION_MARGIN,
+            ) {
+                player.pos.x = new_x;
+                player.pos.y = new_y;
+            }
+        }
+        if controls.right {
+            // Strafe right (perpendicular to viewing direction)
+            let strafe_angle = player.a + PI / 2.0;
+            let new_x = player.pos.x + move_speed * strafe_angle.cos();
+            let new_y = player.pos.y + move_speed * strafe_angle.sin();
+
+            if can_stand(
+                new_x,
+                new_y,
+                player.z,
+                maze,
+                heights,
+                block_size,
+                COLLISION_MARGIN,
+            ) {
+                player.pos.x = new_x;
+                player.pos.y = new_y;
+            }
         }
     }
-    if window.is_key_down(KeyboardKey::KEY_D) {
-        // Strafe right (perpendicular to viewing direction)
-        let strafe_angle = player.a + PI / 2.0;
-        let new_x = player.pos.x + MOVE_SPEED * strafe_angle.cos();
-        let new_y = player.pos.y + MOVE_SPEED * strafe_angle.sin();
-
-        if is_valid_position(new_x, new_y, maze, block_size, COLLISION_MARGIN) {
-            player.pos.x = new_x;
-            player.pos.y = new_y;
+
+    // Stepping into a portal carries the player out of its partner, turned to match
+    if let Some((pos, turn)) = portals.teleport(previous, player.pos, block_size, COLLISION_MARGIN)
+    {
+        player.pos = pos;
+        player.a = (player.a + turn).rem_euclid(2.0 * PI);
+    }
+}
+
+// Jumping, gravity and crouching. Cells with a low ceiling force the player
+// to crouch and cap how high they can jump.
+fn update_height(player: &mut Player, controls: &Controls, heights: &HeightMap, dt: f32) {
+    let ceiling = heights.ceiling_at(player.pos);
+    let low_ceiling = ceiling < f32::MAX;
+    let ground = ground_height(player.pos.x, player.pos.y, heights, COLLISION_MARGIN);
+    let on_ground = player.z <= ground;
+
+    let target_height = if controls.crouch || low_ceiling {
+        CROUCH_EYE_HEIGHT
+    } else {
+        EYE_HEIGHT
+    };
+    let step = CROUCH_SPEED * dt;
+    player.height += (target_height - player.height).clamp(-step, step);
+
+    if on_ground && !player.is_crouching() && controls.jump {
+        player.vz = JUMP_SPEED;
+    }
+
+    // Walking off a ledge falls, walking up a step lands on top of it
+    player.vz -= GRAVITY * dt;
+    player.z += player.vz * dt;
+    if player.z <= ground {
+        player.z = ground;
+        player.vz = 0.0;
+    }
+
+    // Bump the head on low ceilings
+    if low_ceiling {
+        let max_z = (ceiling - HEAD_ROOM - player.height).max(ground);
+        if player.z > max_z {
+            player.z = max_z;
+            player.vz = player.vz.min(0.0);
         }
     }
 }
 
-fn is_valid_position(x: f32, y: f32, maze: &Maze, block_size: usize, margin: f32) -> bool {
-    // Check all four corners of the player's bounding box
-    let positions = [
+// Highest floor or wall top under a bounding box
+pub fn ground_height(x: f32, y: f32, heights: &HeightMap, margin: f32) -> f32 {
+    bounding_corners(x, y, margin)
+        .iter()
+        .map(|&(px, py)| heights.top_at(Vector2::new(px, py)))
+        .fold(0.0, f32::max)
+}
+
+// The player and enemies can move anywhere whose floor is at most a step
+// above their feet, which includes the tops of short walls the player jumps
+// onto
+pub fn can_stand(
+    x: f32,
+    y: f32,
+    z: f32,
+    maze: &Maze,
+    heights: &HeightMap,
+    block_size: usize,
+    margin: f32,
+) -> bool {
+    bounding_corners(x, y, margin)
+        .iter()
+        .all(|&(px, py)| heights.top_at(Vector2::new(px, py)) <= z + STEP_HEIGHT)
+        && clear_of_thin_walls(x, y, maze, block_size, margin)
+}
+
+fn bounding_corners(x: f32, y: f32, margin: f32) -> [(f32, f32); 4] {
+    [
         (x - margin, y - margin),
         (x + margin, y - margin),
         (x - margin, y + margin),
         (x + margin, y + margin),
-    ];
+    ]
+}
+
+pub fn is_valid_position(x: f32, y: f32, maze: &Maze, block_size: usize, margin: f32) -> bool {
+    // Check all four corners of the player's bounding box
+    let positions = bounding_corners(x, y, margin);
 
     for (px, py) in positions.iter() {
         // Convert to maze coordinates
@@ -88,12 +310,12 @@ fn is_valid_position(x: f32, y: f32, maze: &Maze, block_size: usize, margin: f32
             return false;
         }
 
-        // Check if position is in a wall
-        if maze[j][i] != ' ' {
+        // Check if position is in a wall. Thin walls only fill part of their cell.
+        if maze[j][i] != ' ' && ThinWall::from_char(maze[j][i]).is_none() {
             return false;
         }
     }
 
-    true
+    clear_of_thin_walls(x, y, maze, block_size, margin)
 }
 
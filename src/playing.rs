@@ -0,0 +1,226 @@
+use crate::canvas::Canvas;
+use crate::framebuffer::DEFAULT_RAYS;
+use crate::hud::{
+    render_ammo, render_debug, render_lives, render_minimap, render_weapon, scaled, ui_scale,
+};
+use crate::maze::LevelSource;
+use crate::player::Controls;
+use crate::render::{render_sprites, render3d};
+use crate::replay::{EndState, Outcome, Replay};
+use crate::scene::{Context, Scene, Transition};
+use crate::screens::{GameOverScene, LEVELS, PauseScene, VictoryScene};
+use crate::viewport::Viewport;
+use crate::wall_textures::WallTextures;
+use crate::world::{Event, World};
+use raylib::prelude::*;
+use std::rc::Rc;
+
+// A level being played
+pub struct PlayingScene {
+    name: String, // Shown while playing and on the screen after
+    world: World,
+    wall_textures: Rc<WallTextures>,
+    viewport: Viewport,
+    replay: Option<Replay>, // Kept while recording
+    end_state: EndState,
+}
+
+impl PlayingScene {
+    // One of the levels on the level select screen
+    pub fn new(level: usize, context: &Context) -> Result<Self, String> {
+        let level_file = LEVELS.get(level).copied().unwrap_or(LEVELS[0]);
+        Self::load(
+            &format!("Level {}", level + 1),
+            LevelSource::File(level_file.to_string()),
+            context,
+        )
+    }
+
+    // Any level file or generated maze
+    pub fn load(name: &str, source: LevelSource, context: &Context) -> Result<Self, String> {
+        println!("Loading {}", source);
+        let loaded = source.load(&context.enemy_types, &context.assets)?;
+        println!("Loaded {} enemies from level", loaded.enemies.len());
+
+        let mut world = World::new(loaded, context.block_size, &context.assets);
+        world.player.fov = context.fov;
+        Ok(PlayingScene {
+            name: name.to_string(),
+            world,
+            wall_textures: context.wall_textures.clone(),
+            viewport: Viewport::new(context.rays, context.render_scale, context.scaling),
+            replay: context.record.as_ref().map(|_| Replay::new(source)),
+            end_state: EndState::Playing,
+        })
+    }
+}
+
+impl Scene for PlayingScene {
+    fn on_enter(&mut self, window: &mut RaylibHandle, context: &mut Context) {
+        context.audio.play_music();
+        window.disable_cursor();
+    }
+
+    fn on_exit(&mut self, window: &mut RaylibHandle, context: &mut Context) {
+        context.audio.pause_music();
+        window.enable_cursor();
+
+        if let (Some(replay), Some(path)) = (&mut self.replay, &context.record) {
+            replay.end = Some(Outcome::of(&self.world, self.end_state));
+            match replay.save(path) {
+                Ok(()) => println!("Saved replay of {} frames to {}", replay.frames.len(), path),
+                Err(e) => eprintln!("Can't save replay: {}", e),
+            }
+        }
+    }
+
+    fn update(&mut self, window: &mut RaylibHandle, context: &mut Context) -> Transition {
+        // Check for ESC key BEFORE processing other events
+        if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return Transition::Push(Box::new(PauseScene::new()));
+        }
+
+        if window.is_key_pressed(KeyboardKey::KEY_Y) {
+            context.invert_y = !context.invert_y;
+        }
+
+        // Switch between the default ray count and one ray per screen column
+        if window.is_key_pressed(KeyboardKey::KEY_R) {
+            context.rays = if context.rays > DEFAULT_RAYS {
+                DEFAULT_RAYS
+            } else {
+                context.window_width.max(1) as usize
+            };
+        }
+        self.viewport.view.framebuffer.set_rays(context.rays);
+
+        if window.is_key_pressed(KeyboardKey::KEY_F3) {
+            context.debug = !context.debug;
+        }
+
+        // Move everything on by a frame and react to what happened
+        let controls = Controls::read(window, context.invert_y);
+        let dt = window.get_frame_time();
+        if let Some(replay) = &mut self.replay {
+            replay.record(dt, &controls);
+        }
+        for event in self.world.step(&controls, dt) {
+            match event {
+                Event::Shot => context.audio.play_shot_sound(),
+                Event::CheckpointReached => context.audio.play_checkpoint_sound(),
+                Event::Damaged => context.audio.play_damage_sound(),
+                Event::Died => {
+                    self.end_state = EndState::Died;
+                    return Transition::Replace(Box::new(GameOverScene::new(&self.name)));
+                }
+                Event::Won => {
+                    self.end_state = EndState::Won;
+                    return Transition::Replace(Box::new(VictoryScene::new(&self.name)));
+                }
+            }
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, d: &mut dyn Canvas, context: &Context) {
+        render_world(
+            d,
+            &self.world,
+            &self.wall_textures,
+            &mut self.viewport,
+            context.window_width,
+            context.window_height,
+        );
+
+        // Show current level
+        let px = |size| scaled(size, ui_scale(context.window_width, context.window_height));
+        d.draw_text(&self.name, px(10), px(35), px(20), Color::GREEN);
+
+        if context.debug {
+            render_debug(
+                d,
+                &self.world,
+                &self.viewport,
+                context.window_width,
+                context.window_height,
+            );
+        }
+    }
+}
+
+// Draw a whole frame of a level being played: the 3D view, sprites, minimap
+// and HUD
+pub fn render_world(
+    d: &mut dyn Canvas,
+    world: &World,
+    wall_textures: &WallTextures,
+    viewport: &mut Viewport,
+    window_width: i32,
+    window_height: i32,
+) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+
+    d.clear_background(Color::BLACK);
+
+    // The 3D view and sprites, at the viewport's resolution
+    viewport.render(d, window_width, window_height, |canvas, view| {
+        render3d(canvas, world, wall_textures, view);
+        render_sprites(canvas, world, wall_textures, view);
+    });
+
+    if world.combat {
+        render_weapon(d, &world.player, &world.weapon, window_width, window_height);
+    }
+    render_minimap(d, world, window_width, window_height);
+
+    // Render lives at the bottom center
+    render_lives(d, world.lives, window_width, window_height);
+    if world.combat {
+        render_ammo(d, world.weapon.ammo, window_width, window_height);
+    }
+
+    // Flash effect if invulnerable
+    if world.invulnerability_timer > 0.0 {
+        let flash = ((world.invulnerability_timer * 10.0).sin() * 0.5 + 0.5) * 100.0;
+        d.draw_rectangle(
+            0,
+            0,
+            window_width,
+            window_height,
+            Color::new(255, 0, 0, flash as u8),
+        );
+    }
+
+    // Green flash and message when a checkpoint is activated
+    if world.checkpoint_timer > 0.0 {
+        let flash = (world.checkpoint_timer / 2.0) * 60.0;
+        d.draw_rectangle(
+            0,
+            0,
+            window_width,
+            window_height,
+            Color::new(40, 220, 120, flash as u8),
+        );
+
+        let message = "Checkpoint reached!";
+        let message_font_size = px(30);
+        let message_width = d.measure_text(message, message_font_size);
+        d.draw_text(
+            message,
+            (window_width - message_width) / 2,
+            window_height / 4,
+            message_font_size,
+            Color::new(40, 220, 120, 255),
+        );
+    }
+
+    // FPS counter
+    let fps = d.fps();
+    d.draw_text(
+        &format!("FPS: {}", fps),
+        px(10),
+        px(10),
+        px(20),
+        Color::GREEN,
+    );
+}
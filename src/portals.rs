@@ -0,0 +1,112 @@
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+// Two linked cells. Whatever enters one comes out of the other, turned by
+// a number of quarter turns clockwise (undone on the way back).
+struct Portal {
+    from: (usize, usize),
+    to: (usize, usize),
+    turn: f32,
+}
+
+pub struct Portals {
+    portals: Vec<Portal>,
+}
+
+impl Default for Portals {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Portals {
+    pub fn new() -> Self {
+        Portals {
+            portals: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, from: (usize, usize), to: (usize, usize), quarter_turns: i32) {
+        self.portals.push(Portal {
+            from,
+            to,
+            turn: quarter_turns.rem_euclid(4) as f32 * PI / 2.0,
+        });
+    }
+
+    // The cell a portal cell leads to and how far it turns anything passing through
+    pub fn link(&self, cell: (usize, usize)) -> Option<((usize, usize), f32)> {
+        self.portals.iter().find_map(|portal| {
+            if portal.from == cell {
+                Some((portal.to, portal.turn))
+            } else if portal.to == cell {
+                Some((portal.from, -portal.turn))
+            } else {
+                None
+            }
+        })
+    }
+
+    // Both ends of every portal, for the minimap
+    pub fn pairs(&self) -> impl Iterator<Item = ((usize, usize), (usize, usize))> + '_ {
+        self.portals.iter().map(|portal| (portal.from, portal.to))
+    }
+
+    // Carry a point in one portal cell over to the matching point in its partner
+    pub fn carry(
+        from: (usize, usize),
+        to: (usize, usize),
+        turn: f32,
+        point: Vector2,
+        block_size: f32,
+    ) -> Vector2 {
+        let offset = point - cell_center(from, block_size);
+        cell_center(to, block_size) + rotate(offset, turn)
+    }
+
+    // Where the player ends up after moving from `previous` to `pos`, and how
+    // far they turn, if the move stepped into a portal. The landing spot is
+    // kept `margin` away from the cell's edges so the player isn't stuck in
+    // a wall next to it.
+    pub fn teleport(
+        &self,
+        previous: Vector2,
+        pos: Vector2,
+        block_size: usize,
+        margin: f32,
+    ) -> Option<(Vector2, f32)> {
+        let cell = cell_at(pos, block_size)?;
+        if cell_at(previous, block_size) == Some(cell) {
+            return None;
+        }
+        let (to, turn) = self.link(cell)?;
+
+        let block_size = block_size as f32;
+        let limit = block_size / 2.0 - margin - 1.0;
+        let landing = Self::carry(cell, to, turn, pos, block_size) - cell_center(to, block_size);
+        let landing = Vector2::new(
+            landing.x.clamp(-limit, limit),
+            landing.y.clamp(-limit, limit),
+        );
+        Some((cell_center(to, block_size) + landing, turn))
+    }
+}
+
+pub fn rotate(v: Vector2, angle: f32) -> Vector2 {
+    let (sin, cos) = angle.sin_cos();
+    Vector2::new(v.x * cos - v.y * sin, v.x * sin + v.y * cos)
+}
+
+fn cell_center((i, j): (usize, usize), block_size: f32) -> Vector2 {
+    Vector2::new(
+        i as f32 * block_size + block_size / 2.0,
+        j as f32 * block_size + block_size / 2.0,
+    )
+}
+
+fn cell_at(pos: Vector2, block_size: usize) -> Option<(usize, usize)> {
+    if pos.x < 0.0 || pos.y < 0.0 {
+        return None;
+    }
+    Some((pos.x as usize / block_size, pos.y as usize / block_size))
+}
@@ -0,0 +1,99 @@
+use crate::maze::Maze;
+use crate::thin_walls::{ThinWall, crosses_thin_wall};
+use raylib::prelude::*;
+
+const POOL_SIZE: usize = 64;
+const PROJECTILE_SPEED: f32 = 320.0;
+const HIT_RADIUS: f32 = 20.0;
+
+#[derive(Clone)]
+pub struct Projectile {
+    pub pos: Vector2,
+    pub vel: Vector2,
+    pub damage: i32,
+    pub active: bool,
+}
+
+// Fixed-size pool so firing never allocates; inactive slots get reused
+pub struct ProjectilePool {
+    pub projectiles: Vec<Projectile>,
+}
+
+impl Default for ProjectilePool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProjectilePool {
+    pub fn new() -> Self {
+        ProjectilePool {
+            projectiles: vec![
+                Projectile {
+                    pos: Vector2::new(0.0, 0.0),
+                    vel: Vector2::new(0.0, 0.0),
+                    damage: 0,
+                    active: false,
+                };
+                POOL_SIZE
+            ],
+        }
+    }
+
+    // Fire a projectile from `pos` along angle `a`; dropped if the pool is full
+    pub fn spawn(&mut self, pos: Vector2, a: f32, damage: i32) {
+        if let Some(projectile) = self.projectiles.iter_mut().find(|p| !p.active) {
+            projectile.pos = pos;
+            projectile.vel = Vector2::new(PROJECTILE_SPEED * a.cos(), PROJECTILE_SPEED * a.sin());
+            projectile.damage = damage;
+            projectile.active = true;
+        }
+    }
+
+    pub fn update(&mut self, maze: &Maze, block_size: usize, dt: f32) {
+        for projectile in self.projectiles.iter_mut().filter(|p| p.active) {
+            let previous = projectile.pos;
+            projectile.pos.x += projectile.vel.x * dt;
+            projectile.pos.y += projectile.vel.y * dt;
+
+            // Projectiles stop at the first wall cell they enter
+            if projectile.pos.x < 0.0 || projectile.pos.y < 0.0 {
+                projectile.active = false;
+                continue;
+            }
+            let i = projectile.pos.x as usize / block_size;
+            let j = projectile.pos.y as usize / block_size;
+            // Thin walls only stop projectiles that pass through the line itself
+            if j >= maze.len()
+                || i >= maze[0].len()
+                || (maze[j][i] != ' ' && ThinWall::from_char(maze[j][i]).is_none())
+                || crosses_thin_wall(maze, previous, projectile.pos, block_size)
+            {
+                projectile.active = false;
+            }
+        }
+    }
+
+    // Returns the damage dealt by the first projectile touching the player
+    pub fn check_hit(&mut self, player_pos: Vector2) -> i32 {
+        for projectile in self.projectiles.iter_mut().filter(|p| p.active) {
+            let dx = player_pos.x - projectile.pos.x;
+            let dy = player_pos.y - projectile.pos.y;
+            if (dx * dx + dy * dy).sqrt() < HIT_RADIUS {
+                projectile.active = false;
+                return projectile.damage;
+            }
+        }
+        0
+    }
+
+    pub fn clear(&mut self) {
+        for projectile in self.projectiles.iter_mut() {
+            projectile.active = false;
+        }
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &Projectile> {
+        self.projectiles.iter().filter(|p| p.active)
+    }
+}
@@ -0,0 +1,706 @@
+use crate::canvas::Canvas;
+use crate::caster::{LayerKind, cast_ray_layers};
+use crate::depth::{DepthBuffer, PaneSlice};
+use crate::enemy::DEATH_TIME;
+use crate::framebuffer::Strip;
+use crate::heights::LOW_CEILING_HEIGHT;
+use crate::lighting::apply_light;
+use crate::pickup::Pickup;
+use crate::player::{EYE_HEIGHT, Player};
+use crate::viewport::View;
+use crate::wall_textures::WallTextures;
+use crate::world::World;
+use raylib::prelude::*;
+use std::f32::consts::PI;
+use std::ops::Range;
+
+// Cast the world's walls, floors and panes into the view, leaving their
+// depths behind for the sprites
+pub fn render3d(d: &mut dyn Canvas, world: &World, wall_textures: &WallTextures, view: &mut View) {
+    const FLOOR_COLOR: Color = Color::new(70, 64, 58, 255);
+    const LOW_CEILING_COLOR: Color = Color::new(45, 42, 40, 255);
+    const PLATFORM_COLOR: Color = Color::new(92, 86, 78, 255);
+    const PANE_STRIP: i32 = 4;
+    const FLOOR_STRIP: i32 = 6;
+
+    let World {
+        player,
+        maze,
+        light_map,
+        fog,
+        sky,
+        heights,
+        panes,
+        portals,
+        ..
+    } = world;
+    let block_size = world.block_size;
+    let (window_width, window_height) = (view.width, view.height);
+    let View {
+        depth, framebuffer, ..
+    } = view;
+
+    let num_rays = framebuffer.ray_count(window_width);
+    let width = window_width as f32;
+    let height = window_height as f32;
+
+    let hw = width / 2.0;
+    let hh = player.horizon(height);
+    let eye = player.eye_height();
+    let has_low_ceilings = heights.has_low_ceilings();
+
+    depth.clear(window_width as usize);
+
+    let distance_to_projection_plane = hw / (player.fov / 2.0).tan();
+
+    // Sky and floor are drawn in bands so they fade into the fog toward the horizon
+    let bands: Vec<Color> = (0..window_height)
+        .step_by(FLOOR_STRIP as usize)
+        .map(|y| {
+            let row = (y as f32 + FLOOR_STRIP as f32 / 2.0 - hh).abs().max(1.0);
+            let (base_color, plane_height) = if (y as f32) < hh {
+                (Color::new(25, 25, 35, 255), block_size as f32 - eye) // Dark blue-gray sky
+            } else {
+                (Color::BLACK, eye)
+            };
+            let distance = plane_height.max(1.0) * distance_to_projection_plane / row;
+            fog.apply(base_color, distance)
+        })
+        .collect();
+
+    let fov_start = player.a - (player.fov / 2.0);
+    let fov_step = player.fov / num_rays as f32;
+
+    // Screen row of a point `height` above the ground at perpendicular `distance`
+    let row_of =
+        |height: f32, distance: f32| hh + (eye - height) * distance_to_projection_plane / distance;
+    let start_top = heights.top_at(player.pos);
+
+    // Every ray paints its own columns on whichever core is free, and hands
+    // back what the sprite pass needs to know about depth
+    let rays = framebuffer.paint(window_width, window_height, |i, strip| {
+        let mut ray_depth = RayDepth {
+            columns: strip.columns(),
+            ..Default::default()
+        };
+        for (band, &color) in bands.iter().enumerate() {
+            let y = band as i32 * FLOOR_STRIP;
+            strip.fill(y..y + FLOOR_STRIP, color);
+        }
+
+        let a = fov_start + (i as f32 * fov_step);
+        let ray_cos = (a - player.a).cos();
+
+        // Every change in height along the ray; the last one is the wall it ends at
+        let layers = cast_ray_layers(maze, heights, panes, portals, player, a, block_size);
+        let end_layer = layers.last().unwrap();
+        let (end, end_top) = (&end_layer.intersect, &end_layer.top);
+        let end_visible = end.distance <= 4000.0;
+        let end_distance = end.perpendicular_distance.max(10.0);
+
+        // Sprites stop at the first mirror or portal, since everything
+        // further along the ray is only seen in it
+        let first_redirect = layers
+            .iter()
+            .position(|layer| matches!(layer.kind, LayerKind::Redirect(_)));
+        if let Some(k) = first_redirect {
+            ray_depth.wall = Some(layers[k].intersect.perpendicular_distance.max(10.0));
+        } else if end_visible {
+            ray_depth.wall = Some(end_distance);
+        }
+
+        // Floor and sky are cast around the wall at the end of the ray.
+        // Shorter walls and platforms in front of it are drawn over them.
+        let (wall_top, wall_bottom) = if end_visible {
+            (
+                row_of(*end_top, end_distance).max(0.0) as i32,
+                row_of(0.0, end_distance).min(height) as i32,
+            )
+        } else {
+            let horizon = hh.clamp(0.0, height) as i32;
+            (horizon, horizon)
+        };
+
+        // Cast the floor below this column so lights show up on the ground too.
+        // Unlit levels keep their plain black floor.
+        if light_map.is_lit() {
+            for y in (wall_bottom..window_height).step_by(FLOOR_STRIP as usize) {
+                let row = (y as f32 + FLOOR_STRIP as f32 / 2.0 - hh).max(1.0);
+                let floor_distance = eye * distance_to_projection_plane / row / ray_cos;
+                let floor_pos = Vector2::new(
+                    player.pos.x + floor_distance * a.cos(),
+                    player.pos.y + floor_distance * a.sin(),
+                );
+
+                let floor_color = apply_light(FLOOR_COLOR, light_map.sample(floor_pos));
+                strip.fill(y..y + FLOOR_STRIP, fog.apply(floor_color, floor_distance));
+            }
+        }
+
+        // Cast the ceiling above this column and open it up to the sky over
+        // outdoor cells
+        if sky.is_visible() {
+            for y in (0..wall_top).step_by(FLOOR_STRIP as usize) {
+                let row = (hh - y as f32 - FLOOR_STRIP as f32 / 2.0).max(1.0);
+                let ceiling_distance =
+                    (block_size as f32 - eye) * distance_to_projection_plane / row / ray_cos;
+                let ceiling_pos = Vector2::new(
+                    player.pos.x + ceiling_distance * a.cos(),
+                    player.pos.y + ceiling_distance * a.sin(),
+                );
+
+                if sky.is_outdoor(ceiling_pos, block_size) {
+                    strip.fill(
+                        y..y + FLOOR_STRIP,
+                        sky.sample(a, 1.0 - (hh - y as f32) / (height / 2.0)),
+                    );
+                }
+            }
+        }
+
+        // Walls and platforms from the end of the ray back toward the player,
+        // so nearer ones cover whatever they hide
+        for k in (0..layers.len()).rev() {
+            let intersect = &layers[k].intersect;
+            let top = layers[k].top;
+            let is_end = k == layers.len() - 1;
+            if is_end && !end_visible {
+                continue;
+            }
+
+            let corrected_distance = intersect.perpendicular_distance.max(10.0);
+            let below = if k == 0 { start_top } else { layers[k - 1].top };
+
+            // The top of a short wall or platform, when the eyes are above it
+            if !is_end && top > 0.0 && eye > top {
+                let far = &layers[k + 1].intersect;
+                let far_distance = far.perpendicular_distance.max(10.0);
+                let mid_distance = (intersect.distance + far.distance) / 2.0;
+                let mid_pos = (layers[k].light_pos + layers[k + 1].light_pos) * 0.5;
+                let top_color = apply_light(PLATFORM_COLOR, light_map.sample(mid_pos));
+                fill_rows(
+                    strip,
+                    row_of(top, far_distance) as i32..row_of(top, corrected_distance) as i32,
+                    fog.apply(top_color, mid_distance),
+                );
+            }
+
+            // Mirrors and portals wash everything seen in them with their colour
+            if let LayerKind::Redirect(tint) = layers[k].kind {
+                fill_rows(
+                    strip,
+                    row_of(block_size as f32, corrected_distance).max(0.0) as i32
+                        ..row_of(0.0, corrected_distance).min(height) as i32,
+                    tint,
+                );
+                continue;
+            }
+
+            // See-through walls are kept for the sprite pass, which layers
+            // them with the sprites by distance. Those seen in a mirror or
+            // portal have no sprites around them and are drawn straight away.
+            if let LayerKind::Pane(pane_height) = layers[k].kind {
+                if let Some(pane) = panes.get(intersect.impact) {
+                    let scale = distance_to_projection_plane / corrected_distance;
+                    let pane_top = row_of(pane_height, corrected_distance);
+                    let rows = pane_top.max(0.0) as i32
+                        ..row_of(0.0, corrected_distance).min(height) as i32;
+
+                    let pane_light = light_map.sample(layers[k].light_pos);
+
+                    // Merge rows of the same colour and leave out the holes
+                    let mut runs: Vec<(Range<i32>, Color)> = Vec::new();
+                    for y in rows.clone().step_by(PANE_STRIP as usize) {
+                        let strip_end = (y + PANE_STRIP).min(rows.end);
+                        let below_top = (y as f32 - pane_top) / scale;
+                        let tex_y = ((below_top / block_size as f32) * 128.0) as usize % 128;
+                        let color = pane.pixel(intersect.tx, tex_y);
+                        if color.a == 0 {
+                            continue;
+                        }
+
+                        let color = fog.apply(apply_light(color, pane_light), corrected_distance);
+                        match runs.last_mut() {
+                            Some((run, run_color)) if run.end == y && *run_color == color => {
+                                run.end = strip_end;
+                            }
+                            _ => runs.push((y..strip_end, color)),
+                        }
+                    }
+
+                    if first_redirect.is_some_and(|redirect| k > redirect) {
+                        for (rows, color) in runs {
+                            fill_rows(strip, rows, color);
+                        }
+                    } else {
+                        ray_depth.panes.push(PaneSlice {
+                            columns: strip.columns(),
+                            distance: corrected_distance,
+                            runs,
+                        });
+                    }
+                }
+                continue;
+            }
+
+            // Only the side facing the player is visible, rising from the
+            // lower cell in front of it
+            if top > below || is_end {
+                // Walls rise from the floor to their top, seen from eye height
+                let scale = distance_to_projection_plane / corrected_distance;
+                let wall_top_unclamped = row_of(top, corrected_distance);
+                let wall_top = wall_top_unclamped.max(0.0) as i32;
+                let wall_bottom = row_of(0.0, corrected_distance).min(height) as i32;
+
+                // Walls take the light of the open space just in front of them
+                let wall_light = light_map.sample(layers[k].light_pos);
+
+                // Nothing to draw when the wall is entirely off screen
+                if wall_bottom > wall_top {
+                    let strip_height = if corrected_distance < 50.0 {
+                        16
+                    } else if corrected_distance < 100.0 {
+                        8
+                    } else {
+                        4
+                    };
+
+                    let max_strips = 50;
+                    let actual_strip_height =
+                        ((wall_bottom - wall_top) / max_strips).max(strip_height);
+
+                    for y in (wall_top..wall_bottom).step_by(actual_strip_height as usize) {
+                        let strip_end = (y + actual_strip_height).min(wall_bottom);
+
+                        // The texture hangs from the top of the wall and repeats every block
+                        let below_top = (y as f32 - wall_top_unclamped) / scale;
+                        let tex_y = ((below_top / block_size as f32) * 128.0) as usize % 128;
+
+                        let color = wall_textures.get_pixel(intersect.tx, tex_y, intersect.impact);
+
+                        let lit_color = apply_light(color, wall_light);
+
+                        strip.fill(y..strip_end, fog.apply(lit_color, corrected_distance));
+                    }
+                }
+            }
+
+            // Sprites behind a short wall are hidden below its top edge
+            if !is_end && top > 0.0 {
+                let top_row = row_of(top, corrected_distance) as i32;
+                ray_depth.ledges.push((corrected_distance, top_row));
+            }
+        }
+
+        // The top of whatever the player is standing on
+        if start_top > 0.0 && eye > start_top {
+            let first = &layers[0].intersect;
+            let near_distance = first.distance / 2.0;
+            let near_pos = Vector2::new(
+                player.pos.x + near_distance * a.cos(),
+                player.pos.y + near_distance * a.sin(),
+            );
+            let top_color = apply_light(PLATFORM_COLOR, light_map.sample(near_pos));
+            fill_rows(
+                strip,
+                row_of(start_top, first.perpendicular_distance.max(10.0)) as i32..window_height,
+                fog.apply(top_color, near_distance),
+            );
+        }
+
+        // Low ceilings are drawn over the top of the walls behind them, on
+        // every row where the ceiling is nearer than the wall
+        if has_low_ceilings && LOW_CEILING_HEIGHT - eye > 1.0 {
+            let ceiling_above = LOW_CEILING_HEIGHT - eye;
+            let last_row = (hh - ceiling_above * distance_to_projection_plane / end_distance)
+                .clamp(0.0, height) as i32;
+            for y in (0..last_row).step_by(FLOOR_STRIP as usize) {
+                let row = (hh - y as f32 - FLOOR_STRIP as f32 / 2.0).max(1.0);
+                let ceiling_distance = ceiling_above * distance_to_projection_plane / row / ray_cos;
+                let ceiling_pos = Vector2::new(
+                    player.pos.x + ceiling_distance * a.cos(),
+                    player.pos.y + ceiling_distance * a.sin(),
+                );
+
+                if heights.ceiling_at(ceiling_pos) < f32::MAX {
+                    let ceiling_color =
+                        apply_light(LOW_CEILING_COLOR, light_map.sample(ceiling_pos));
+                    strip.fill(
+                        y..(y + FLOOR_STRIP).min(last_row),
+                        fog.apply(ceiling_color, ceiling_distance),
+                    );
+                }
+            }
+        }
+        ray_depth
+    });
+
+    // Hand the depth of each ray to the columns it covered, then put the
+    // view on screen
+    for ray_depth in rays {
+        for column in ray_depth.columns.start as usize..ray_depth.columns.end as usize {
+            if let Some(wall) = ray_depth.wall {
+                depth.set_wall(column, wall);
+            }
+            for &(distance, top_row) in &ray_depth.ledges {
+                depth.add_ledge(column, distance, top_row);
+            }
+        }
+        for pane in ray_depth.panes {
+            depth.add_pane(pane);
+        }
+    }
+    framebuffer.draw(d);
+}
+
+// What one ray leaves in the depth buffer for each of its columns
+#[derive(Default)]
+struct RayDepth {
+    columns: Range<i32>,
+    wall: Option<f32>,
+    ledges: Vec<(f32, i32)>,
+    panes: Vec<PaneSlice>,
+}
+
+// Fill some rows of one ray column, such as the flat top of a platform
+fn fill_rows(strip: &mut Strip, rows: Range<i32>, color: Color) {
+    if rows.end > rows.start {
+        strip.fill(rows, color);
+    }
+}
+
+// A world-space sprite for the billboard pass
+struct Billboard {
+    pos: Vector2,
+    width: f32,                     // World units
+    height: f32,                    // World units
+    elevation: f32,                 // Height of the bottom edge above the floor
+    texture: Option<(char, usize)>, // Sprite key and frame; None draws a solid block
+    tint: Color,
+}
+
+impl Billboard {
+    // Square billboard for a sprite, sized and aligned as its sheet asks
+    fn sprite(wall_textures: &WallTextures, key: char, frame: usize, pos: Vector2) -> Self {
+        let (size, floor_aligned) = wall_textures.sprite_placement(key);
+        Billboard {
+            pos,
+            width: size,
+            height: size,
+            elevation: if floor_aligned {
+                0.0
+            } else {
+                EYE_HEIGHT - size / 2.0
+            },
+            texture: Some((key, frame)),
+            tint: Color::WHITE,
+        }
+    }
+}
+
+// Collect every billboard in the level and hand them to the shared sprite pass
+pub fn render_sprites(
+    d: &mut dyn Canvas,
+    world: &World,
+    wall_textures: &WallTextures,
+    view: &View,
+) {
+    let player = &world.player;
+    let time = d.time() as f32;
+    let mut billboards = Vec::new();
+
+    for enemy in &world.enemies {
+        if enemy.is_gone() || !wall_textures.has_sprite(enemy.texture_key) {
+            continue;
+        }
+
+        // Pick the rotation showing the side of the enemy that faces the player.
+        // The player is seen from the enemy in the direction opposite sprite_angle.
+        let sprite_angle = (enemy.pos.y - player.pos.y).atan2(enemy.pos.x - player.pos.x);
+        let angles = wall_textures.sprite_angles(enemy.texture_key);
+        let sector = 2.0 * PI / angles as f32;
+        let view_angle = (sprite_angle + PI - enemy.heading).rem_euclid(2.0 * PI);
+        let rotation = ((view_angle + sector / 2.0) / sector) as usize % angles;
+
+        let frame = wall_textures.sprite_frame(enemy.texture_key, &enemy.animation, rotation);
+        let mut billboard = Billboard::sprite(wall_textures, enemy.texture_key, frame, enemy.pos);
+
+        // Defeated enemies fade to red while their die sequence plays
+        if !enemy.is_alive() {
+            let fade = (enemy.death_timer / DEATH_TIME).max(0.0);
+            let channel = (255.0 * fade) as u8;
+            billboard.tint = Color::new(255, channel, channel, channel);
+        }
+        billboards.push(billboard);
+    }
+
+    if let Some(pos) = world.finish_pos {
+        let frame = wall_textures.sprite_frame('w', &world.finish_animation, 0);
+        billboards.push(Billboard::sprite(wall_textures, 'w', frame, pos));
+    }
+
+    for checkpoint in &world.checkpoints {
+        // Checkpoints are drawn as a thin beam of light standing on the floor.
+        // Activated checkpoints glow green and pulse, inactive ones stay dim.
+        let tint = if checkpoint.activated {
+            let pulse = (time * 4.0).sin() * 0.5 + 0.5;
+            Color::new(40, 220, 120, (140.0 + pulse * 100.0) as u8)
+        } else {
+            Color::new(140, 140, 170, 120)
+        };
+        billboards.push(Billboard {
+            pos: checkpoint.pos,
+            width: 15.0,
+            height: 100.0,
+            elevation: 0.0,
+            texture: None,
+            tint,
+        });
+    }
+
+    // Pickups only show on levels with combat
+    let ammo_pickups: &[Pickup] = if world.combat {
+        &world.ammo_pickups
+    } else {
+        &[]
+    };
+    for pickup in ammo_pickups {
+        let frame = wall_textures.sprite_frame('a', &pickup.animation, 0);
+        billboards.push(Billboard::sprite(wall_textures, 'a', frame, pickup.pos));
+    }
+
+    // Everything so far stands on the floor, so it rises with raised platforms
+    for billboard in billboards.iter_mut() {
+        billboard.elevation += world.heights.top_at(billboard.pos);
+    }
+
+    for projectile in world.projectiles.active() {
+        billboards.push(Billboard::sprite(wall_textures, '*', 0, projectile.pos));
+    }
+
+    render_billboards(d, world, &mut billboards, wall_textures, view);
+}
+
+// Shared sprite pass: billboards are sorted by distance and drawn far to near,
+// so closer sprites always cover the ones behind them
+fn render_billboards(
+    d: &mut dyn Canvas,
+    world: &World,
+    billboards: &mut [Billboard],
+    wall_textures: &WallTextures,
+    view: &View,
+) {
+    let World {
+        player,
+        light_map,
+        fog,
+        ..
+    } = world;
+    let (window_width, window_height, depth) = (view.width, view.height, &view.depth);
+    let hw = window_width as f32 / 2.0;
+    let hh = player.horizon(window_height as f32);
+    let distance_to_projection_plane = hw / (player.fov / 2.0).tan();
+
+    // Sort billboards by distance (furthest first)
+    let distance_to = |billboard: &Billboard| {
+        let dx = billboard.pos.x - player.pos.x;
+        let dy = billboard.pos.y - player.pos.y;
+        dx * dx + dy * dy
+    };
+    billboards.sort_by(|a, b| distance_to(b).partial_cmp(&distance_to(a)).unwrap());
+
+    // See-through walls are layered in with the sprites, furthest first too
+    let mut panes: Vec<&PaneSlice> = depth.panes().iter().collect();
+    panes.sort_by(|a, b| b.distance.partial_cmp(&a.distance).unwrap());
+    let mut panes = panes.into_iter().peekable();
+
+    for billboard in billboards.iter() {
+        let (angle_diff, distance) = sprite_view(player, billboard.pos);
+
+        // Panes behind this sprite go underneath it
+        while let Some(pane) = panes.next_if(|pane| pane.distance > distance) {
+            draw_pane(d, pane, depth);
+        }
+
+        // Sprites lost in the fog are skipped entirely
+        if angle_diff.abs() > player.fov / 2.0 + 0.2
+            || distance < 10.0
+            || distance > fog.cull_distance()
+        {
+            continue;
+        }
+
+        let scale = distance_to_projection_plane / distance;
+        let sprite_width = (billboard.width * scale).max(1.0);
+        let sprite_height = billboard.height * scale;
+        let bottom = hh + (player.eye_height() - billboard.elevation) * scale;
+
+        let screen_x = hw + (angle_diff.tan() * distance_to_projection_plane);
+        let x_start = (screen_x - sprite_width / 2.0) as i32;
+        let x_end = (screen_x + sprite_width / 2.0).max(x_start as f32 + 1.0) as i32;
+        let y_start = (bottom - sprite_height) as i32;
+        let y_end = bottom as i32;
+
+        if x_end < 0 || x_start >= window_width || y_end < 0 || y_start >= window_height {
+            continue;
+        }
+
+        let clipped_x_start = x_start.max(0);
+        let clipped_x_end = x_end.min(window_width);
+        let clipped_y_start = y_start.max(0);
+        let clipped_y_end = y_end.min(window_height);
+
+        let tint = billboard.tint;
+        let light = light_map.sample(billboard.pos);
+
+        let Some((key, frame)) = billboard.texture else {
+            // Untextured billboards are a single block of their tint
+            draw_clipped(
+                d,
+                clipped_x_start..clipped_x_end,
+                clipped_y_start,
+                clipped_y_end - clipped_y_start,
+                fog.apply(apply_light(tint, light), distance),
+                distance,
+                depth,
+            );
+            continue;
+        };
+
+        // Dynamic strip width based on sprite size to maintain performance
+        let sprite_screen_width = clipped_x_end - clipped_x_start;
+        let strip_width = if sprite_screen_width > 300 {
+            16 // Very large sprite
+        } else if sprite_screen_width > 150 {
+            8 // Large sprite
+        } else if sprite_screen_width > 75 {
+            4 // Medium sprite
+        } else {
+            2 // Small sprite - keep detail
+        };
+
+        // Dynamic vertical strip height for large sprites
+        let sprite_screen_height = clipped_y_end - clipped_y_start;
+        let y_strip = if sprite_screen_height > 400 {
+            12 // Very tall sprite
+        } else if sprite_screen_height > 200 {
+            8 // Tall sprite
+        } else if sprite_screen_height > 40 {
+            4 // Normal height
+        } else {
+            2 // Small sprite - keep detail
+        };
+
+        // Limit total strips for performance
+        let max_x_strips = 30;
+        let actual_strip_width = (sprite_screen_width / max_x_strips).max(strip_width);
+
+        for x in (clipped_x_start..clipped_x_end).step_by(actual_strip_width as usize) {
+            let strip_end = (x + actual_strip_width).min(clipped_x_end);
+
+            // Skip strips that are entirely behind walls
+            if (x..strip_end).all(|column| depth.visible_until(column as usize, distance).is_none())
+            {
+                continue;
+            }
+
+            let tex_x = (((x - x_start) as f32 / sprite_width * 128.0) as usize).min(127);
+
+            for y in (clipped_y_start..clipped_y_end).step_by(y_strip) {
+                let strip_height = (y + y_strip as i32).min(clipped_y_end) - y;
+                let tex_y = (((y - y_start) as f32 / sprite_height * 128.0) as usize).min(127);
+
+                let color = wall_textures.get_frame_pixel(tex_x, tex_y, key, frame);
+
+                // Fully transparent texels let the scene behind show through
+                if color.a == 0 {
+                    continue;
+                }
+
+                let tinted_color = Color::new(
+                    (color.r as f32 * tint.r as f32 / 255.0) as u8,
+                    (color.g as f32 * tint.g as f32 / 255.0) as u8,
+                    (color.b as f32 * tint.b as f32 / 255.0) as u8,
+                    (color.a as f32 * tint.a as f32 / 255.0) as u8,
+                );
+
+                draw_clipped(
+                    d,
+                    x..strip_end,
+                    y,
+                    strip_height,
+                    fog.apply(apply_light(tinted_color, light), distance),
+                    distance,
+                    depth,
+                );
+            }
+        }
+    }
+
+    // Panes nearer than every sprite go on top
+    for pane in panes {
+        draw_pane(d, pane, depth);
+    }
+}
+
+fn draw_pane(d: &mut dyn Canvas, pane: &PaneSlice, depth: &DepthBuffer) {
+    for (rows, color) in &pane.runs {
+        draw_clipped(
+            d,
+            pane.columns.clone(),
+            rows.start,
+            rows.end - rows.start,
+            *color,
+            pane.distance,
+            depth,
+        );
+    }
+}
+
+// Fill a block of screen with one sprite colour, leaving out every column
+// where a wall is closer than the sprite and every row hidden behind a
+// shorter wall in front of it
+fn draw_clipped(
+    d: &mut dyn Canvas,
+    columns: Range<i32>,
+    y: i32,
+    height: i32,
+    color: Color,
+    distance: f32,
+    depth: &DepthBuffer,
+) {
+    let mut x = columns.start.max(0);
+    let x_end = columns.end.min(depth.width() as i32);
+
+    while x < x_end {
+        let Some(visible_until) = depth.visible_until(x as usize, distance) else {
+            x += 1;
+            continue;
+        };
+
+        // Draw the whole run of columns visible down to the same row as one rectangle
+        let run_start = x;
+        while x < x_end && depth.visible_until(x as usize, distance) == Some(visible_until) {
+            x += 1;
+        }
+        let bottom = (y + height).min(visible_until);
+        if bottom > y {
+            d.draw_rectangle(run_start, y, x - run_start, bottom - y, color);
+        }
+    }
+}
+
+// Angle between the view direction and a sprite (in -PI..PI) and its distance
+fn sprite_view(player: &Player, pos: Vector2) -> (f32, f32) {
+    let dx = pos.x - player.pos.x;
+    let dy = pos.y - player.pos.y;
+    let distance = (dx * dx + dy * dy).sqrt();
+    let mut angle_diff = dy.atan2(dx) - player.a;
+    while angle_diff > PI {
+        angle_diff -= 2.0 * PI;
+    }
+    while angle_diff < -PI {
+        angle_diff += 2.0 * PI;
+    }
+    (angle_diff, distance)
+}
@@ -0,0 +1,191 @@
+use crate::assets::Assets;
+use crate::enemy::EnemyType;
+use crate::maze::LevelSource;
+use crate::player::Controls;
+use crate::world::{Event, World};
+use std::fs;
+
+// The controls of every frame of one level played, and where it ended up.
+// The game is driven only by its controls and frame times, so stepping a
+// fresh world through the same frames must end in the same place.
+//
+// Replay files are plain text:
+//   level level1.txt
+//   frame 0.016667 w--d--- 0.012 0
+//   end 412.5 150 2 12 playing
+// Each frame holds the frame time, the held keys (forward, back, left,
+// right, crouch, jump, fire, or '-' when up), and the turn and look amounts.
+pub struct Replay {
+    pub level: LevelSource,
+    pub frames: Vec<(f32, Controls)>,
+    pub end: Option<Outcome>,
+}
+
+// Where a replay left the player
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Outcome {
+    pub x: f32,
+    pub y: f32,
+    pub lives: i32,
+    pub ammo: i32,
+    pub state: EndState,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EndState {
+    Playing,
+    Won,
+    Died,
+}
+
+const KEYS: [char; 7] = ['w', 's', 'a', 'd', 'c', 'j', 'f'];
+
+impl Outcome {
+    pub fn of(world: &World, state: EndState) -> Self {
+        Outcome {
+            x: world.player.pos.x,
+            y: world.player.pos.y,
+            lives: world.lives,
+            ammo: world.weapon.ammo,
+            state,
+        }
+    }
+
+    // Positions are compared with a little slack, since trigonometry may
+    // round differently on another machine
+    fn matches(&self, other: &Outcome) -> bool {
+        (self.x - other.x).abs() < 0.01
+            && (self.y - other.y).abs() < 0.01
+            && self.lives == other.lives
+            && self.ammo == other.ammo
+            && self.state == other.state
+    }
+}
+
+impl Replay {
+    pub fn new(level: LevelSource) -> Self {
+        Replay {
+            level,
+            frames: Vec::new(),
+            end: None,
+        }
+    }
+
+    pub fn record(&mut self, dt: f32, controls: &Controls) {
+        self.frames.push((dt, *controls));
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut text = format!("level {}\n", self.level);
+        for (dt, controls) in &self.frames {
+            let held = [
+                controls.forward,
+                controls.back,
+                controls.left,
+                controls.right,
+                controls.crouch,
+                controls.jump,
+                controls.fire,
+            ];
+            let keys: String = KEYS
+                .iter()
+                .zip(held)
+                .map(|(&key, down)| if down { key } else { '-' })
+                .collect();
+            text += &format!(
+                "frame {} {} {} {}\n",
+                dt, keys, controls.turn, controls.look
+            );
+        }
+        if let Some(end) = self.end {
+            let state = match end.state {
+                EndState::Playing => "playing",
+                EndState::Won => "won",
+                EndState::Died => "died",
+            };
+            text += &format!(
+                "end {} {} {} {} {}\n",
+                end.x, end.y, end.lives, end.ammo, state
+            );
+        }
+        fs::write(path, text).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut replay = Replay::new(LevelSource::File(String::new()));
+        for (number, line) in text.lines().enumerate() {
+            let bad_line = || format!("{}:{}: bad line '{}'", path, number + 1, line);
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [] => {}
+                ["level", ..] => replay.level = line.trim()["level".len()..].trim().parse()?,
+                ["frame", dt, keys, turn, look] if keys.chars().count() == KEYS.len() => {
+                    let held: Vec<bool> = keys.chars().map(|key| key != '-').collect();
+                    let controls = Controls {
+                        forward: held[0],
+                        back: held[1],
+                        left: held[2],
+                        right: held[3],
+                        crouch: held[4],
+                        jump: held[5],
+                        fire: held[6],
+                        turn: turn.parse().map_err(|_| bad_line())?,
+                        look: look.parse().map_err(|_| bad_line())?,
+                    };
+                    replay.record(dt.parse().map_err(|_| bad_line())?, &controls);
+                }
+                ["end", x, y, lives, ammo, state] => {
+                    replay.end = Some(Outcome {
+                        x: x.parse().map_err(|_| bad_line())?,
+                        y: y.parse().map_err(|_| bad_line())?,
+                        lives: lives.parse().map_err(|_| bad_line())?,
+                        ammo: ammo.parse().map_err(|_| bad_line())?,
+                        state: match *state {
+                            "playing" => EndState::Playing,
+                            "won" => EndState::Won,
+                            "died" => EndState::Died,
+                            _ => return Err(bad_line()),
+                        },
+                    });
+                }
+                _ => return Err(bad_line()),
+            }
+        }
+        if replay.level == LevelSource::File(String::new()) {
+            return Err(format!("{}: no level line", path));
+        }
+        Ok(replay)
+    }
+
+    // Step a fresh world through every frame and check it ends where the
+    // recording did
+    pub fn verify(
+        &self,
+        enemy_types: &[EnemyType],
+        assets: &Assets,
+        block_size: usize,
+    ) -> Result<Outcome, String> {
+        let level = self.level.load(enemy_types, assets)?;
+        let mut world = World::new(level, block_size, assets);
+        let mut state = EndState::Playing;
+        for (dt, controls) in &self.frames {
+            for event in world.step(controls, *dt) {
+                match event {
+                    Event::Won => state = EndState::Won,
+                    Event::Died => state = EndState::Died,
+                    _ => {}
+                }
+            }
+        }
+
+        let outcome = Outcome::of(&world, state);
+        match self.end {
+            Some(end) if !end.matches(&outcome) => Err(format!(
+                "Replay ended at {:?}, but was recorded ending at {:?}",
+                outcome, end
+            )),
+            _ => Ok(outcome),
+        }
+    }
+}
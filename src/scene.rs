@@ -0,0 +1,129 @@
+use crate::assets::Assets;
+use crate::audio::AudioManager;
+use crate::canvas::{Canvas, Scaling};
+use crate::enemy::EnemyType;
+use crate::wall_textures::WallTextures;
+use raylib::prelude::*;
+use std::rc::Rc;
+
+// Everything the scenes share: window size, level data, assets, audio and
+// settings
+pub struct Context {
+    pub window_width: i32,
+    pub window_height: i32,
+    pub block_size: usize,
+    pub enemy_types: Vec<EnemyType>,
+    pub assets: Assets,
+    pub wall_textures: Rc<WallTextures>, // Loaded once and shared by every level
+    pub audio: AudioManager,
+    pub invert_y: bool,         // Mouse look, toggled with Y while playing
+    pub rays: usize,            // Rays cast across the 3D view, at most one per screen column
+    pub fov: f32,               // Field of view in radians
+    pub render_scale: f32,      // Resolution of the 3D view as a fraction of the window's
+    pub scaling: Scaling,       // How the 3D view is stretched to fill the window
+    pub debug: bool,            // Debug overlay, toggled with F3 while playing
+    pub record: Option<String>, // Replay file written when a level ends
+}
+
+// What the scene stack should do after a scene's update
+pub enum Transition {
+    None,
+    Push(Box<dyn Scene>),    // Cover the current scene, which comes back on Pop
+    Pop,                     // Back to the scene underneath
+    Replace(Box<dyn Scene>), // Swap the current scene for another
+    Reset(Box<dyn Scene>),   // Drop every scene and start over from this one
+    Quit,
+}
+
+// One screen of the game. Only the top scene of the stack is updated, but
+// overlays let the scenes under them keep drawing.
+pub trait Scene {
+    // Called when the scene is added to the stack and when it is taken off
+    fn on_enter(&mut self, _window: &mut RaylibHandle, _context: &mut Context) {}
+    fn on_exit(&mut self, _window: &mut RaylibHandle, _context: &mut Context) {}
+
+    fn update(&mut self, window: &mut RaylibHandle, context: &mut Context) -> Transition;
+    fn render(&mut self, d: &mut dyn Canvas, context: &Context);
+
+    // Whether the scene underneath still shows through, as with a pause menu
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+}
+
+impl Default for SceneStack {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SceneStack {
+    pub fn new() -> Self {
+        SceneStack { scenes: Vec::new() }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty()
+    }
+
+    pub fn push(
+        &mut self,
+        mut scene: Box<dyn Scene>,
+        window: &mut RaylibHandle,
+        context: &mut Context,
+    ) {
+        scene.on_enter(window, context);
+        self.scenes.push(scene);
+    }
+
+    pub fn pop(&mut self, window: &mut RaylibHandle, context: &mut Context) {
+        if let Some(mut scene) = self.scenes.pop() {
+            scene.on_exit(window, context);
+        }
+    }
+
+    // Take every scene off, top first, so each one's on_exit runs
+    pub fn clear(&mut self, window: &mut RaylibHandle, context: &mut Context) {
+        while !self.is_empty() {
+            self.pop(window, context);
+        }
+    }
+
+    // Update the top scene and carry out the transition it asks for
+    pub fn update(&mut self, window: &mut RaylibHandle, context: &mut Context) {
+        let Some(scene) = self.scenes.last_mut() else {
+            return;
+        };
+
+        match scene.update(window, context) {
+            Transition::None => {}
+            Transition::Push(scene) => self.push(scene, window, context),
+            Transition::Pop => self.pop(window, context),
+            Transition::Replace(scene) => {
+                self.pop(window, context);
+                self.push(scene, window, context);
+            }
+            Transition::Reset(scene) => {
+                self.clear(window, context);
+                self.push(scene, window, context);
+            }
+            Transition::Quit => self.clear(window, context),
+        }
+    }
+
+    // Draw the top scene, along with the scenes under it when it is an overlay
+    pub fn render(&mut self, d: &mut dyn Canvas, context: &Context) {
+        let first = self
+            .scenes
+            .iter()
+            .rposition(|scene| !scene.is_overlay())
+            .unwrap_or(0);
+        for scene in self.scenes[first..].iter_mut() {
+            scene.render(d, context);
+        }
+    }
+}
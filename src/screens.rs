@@ -0,0 +1,517 @@
+use crate::canvas::Canvas;
+use crate::hud::{scaled, ui_scale};
+use crate::playing::PlayingScene;
+use crate::scene::{Context, Scene, Transition};
+use raylib::prelude::*;
+
+// Level files in the order they are listed on the level select screen
+pub const LEVELS: [&str; 3] = ["level1.txt", "level2.txt", "level3.txt"];
+
+struct MenuOption {
+    text: String,
+    action: fn() -> Transition,
+}
+
+fn start_game() -> Transition {
+    Transition::Push(Box::new(LevelSelectScene::new()))
+}
+
+fn quit_game() -> Transition {
+    Transition::Quit
+}
+
+fn resume_game() -> Transition {
+    Transition::Pop
+}
+
+fn quit_to_menu() -> Transition {
+    Transition::Reset(Box::new(MenuScene::new()))
+}
+
+fn menu_options() -> Vec<MenuOption> {
+    vec![
+        MenuOption {
+            text: "Start".to_string(),
+            action: start_game,
+        },
+        MenuOption {
+            text: "Quit".to_string(),
+            action: quit_game,
+        },
+    ]
+}
+
+fn pause_options() -> Vec<MenuOption> {
+    vec![
+        MenuOption {
+            text: "Resume".to_string(),
+            action: resume_game,
+        },
+        MenuOption {
+            text: "Quit to Menu".to_string(),
+            action: quit_to_menu,
+        },
+    ]
+}
+
+// Move a selection up or down a list with the arrow keys
+fn select(window: &RaylibHandle, selected: &mut usize, count: usize) {
+    if window.is_key_pressed(KeyboardKey::KEY_UP) && *selected > 0 {
+        *selected -= 1;
+    }
+    if window.is_key_pressed(KeyboardKey::KEY_DOWN) && *selected < count - 1 {
+        *selected += 1;
+    }
+}
+
+pub struct MenuScene {
+    selected_option: usize,
+}
+
+impl MenuScene {
+    pub fn new() -> Self {
+        MenuScene { selected_option: 0 }
+    }
+}
+
+impl Default for MenuScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene for MenuScene {
+    fn update(&mut self, window: &mut RaylibHandle, _context: &mut Context) -> Transition {
+        let options = menu_options();
+        select(window, &mut self.selected_option, options.len());
+        if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            return (options[self.selected_option].action)();
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, d: &mut dyn Canvas, context: &Context) {
+        render_menu(
+            d,
+            context.window_width,
+            context.window_height,
+            self.selected_option,
+        );
+    }
+}
+
+pub struct LevelSelectScene {
+    selected_level: usize,
+}
+
+impl LevelSelectScene {
+    pub fn new() -> Self {
+        LevelSelectScene { selected_level: 0 }
+    }
+}
+
+impl Default for LevelSelectScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene for LevelSelectScene {
+    fn update(&mut self, window: &mut RaylibHandle, context: &mut Context) -> Transition {
+        select(window, &mut self.selected_level, LEVELS.len());
+        if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            // Go back to main menu
+            return Transition::Pop;
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            // A missing level file leaves the player on this screen
+            match PlayingScene::new(self.selected_level, context) {
+                Ok(playing) => return Transition::Replace(Box::new(playing)),
+                Err(message) => eprintln!("{}", message),
+            }
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, d: &mut dyn Canvas, context: &Context) {
+        render_level_select(
+            d,
+            context.window_width,
+            context.window_height,
+            self.selected_level,
+        );
+    }
+}
+
+// Shown over the level while it is paused
+pub struct PauseScene {
+    selected_option: usize,
+}
+
+impl PauseScene {
+    pub fn new() -> Self {
+        PauseScene { selected_option: 0 }
+    }
+}
+
+impl Default for PauseScene {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scene for PauseScene {
+    fn on_enter(&mut self, window: &mut RaylibHandle, context: &mut Context) {
+        context.audio.pause_music();
+        window.enable_cursor();
+    }
+
+    fn on_exit(&mut self, window: &mut RaylibHandle, context: &mut Context) {
+        context.audio.play_music();
+        window.disable_cursor();
+    }
+
+    fn update(&mut self, window: &mut RaylibHandle, _context: &mut Context) -> Transition {
+        let options = pause_options();
+        select(window, &mut self.selected_option, options.len());
+        if window.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
+            return Transition::Pop;
+        }
+        if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            return (options[self.selected_option].action)();
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, d: &mut dyn Canvas, context: &Context) {
+        render_pause(
+            d,
+            context.window_width,
+            context.window_height,
+            self.selected_option,
+        );
+    }
+
+    fn is_overlay(&self) -> bool {
+        true
+    }
+}
+
+// Shown after reaching the finish until ENTER goes back to the main menu
+pub struct VictoryScene {
+    level_name: String,
+}
+
+impl VictoryScene {
+    pub fn new(level_name: &str) -> Self {
+        VictoryScene {
+            level_name: level_name.to_string(),
+        }
+    }
+}
+
+impl Scene for VictoryScene {
+    fn update(&mut self, window: &mut RaylibHandle, _context: &mut Context) -> Transition {
+        if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            return Transition::Reset(Box::new(MenuScene::new()));
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, d: &mut dyn Canvas, context: &Context) {
+        render_victory(
+            d,
+            context.window_width,
+            context.window_height,
+            &self.level_name,
+        );
+    }
+}
+
+// Shown after losing every life until ENTER goes back to the main menu
+pub struct GameOverScene {
+    level_name: String,
+}
+
+impl GameOverScene {
+    pub fn new(level_name: &str) -> Self {
+        GameOverScene {
+            level_name: level_name.to_string(),
+        }
+    }
+}
+
+impl Scene for GameOverScene {
+    fn update(&mut self, window: &mut RaylibHandle, _context: &mut Context) -> Transition {
+        if window.is_key_pressed(KeyboardKey::KEY_ENTER) {
+            return Transition::Reset(Box::new(MenuScene::new()));
+        }
+        Transition::None
+    }
+
+    fn render(&mut self, d: &mut dyn Canvas, context: &Context) {
+        render_game_over(
+            d,
+            context.window_width,
+            context.window_height,
+            &self.level_name,
+        );
+    }
+}
+
+pub fn render_menu(
+    d: &mut dyn Canvas,
+    window_width: i32,
+    window_height: i32,
+    selected_option: usize,
+) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    d.clear_background(Color::new(30, 30, 40, 255));
+
+    let title = "Raycaster Game";
+    let title_font_size = px(60);
+    let title_width = d.measure_text(title, title_font_size);
+    let title_x = (window_width - title_width) / 2;
+    let title_y = window_height / 4;
+
+    d.draw_text(title, title_x, title_y, title_font_size, Color::WHITE);
+
+    // Menu options
+    let options = menu_options();
+
+    let option_font_size = px(40);
+    let option_spacing = px(60);
+    let options_start_y = window_height / 2;
+
+    for (i, option) in options.iter().enumerate() {
+        let option_width = d.measure_text(&option.text, option_font_size);
+        let option_x = (window_width - option_width) / 2;
+        let option_y = options_start_y + (i as i32 * option_spacing);
+
+        let color = if i == selected_option {
+            Color::YELLOW
+        } else {
+            Color::LIGHTGRAY
+        };
+
+        d.draw_text(&option.text, option_x, option_y, option_font_size, color);
+
+        // Draw selection indicator
+        if i == selected_option {
+            let arrow_x = option_x - px(40);
+            d.draw_text(">", arrow_x, option_y, option_font_size, Color::YELLOW);
+        }
+    }
+
+    // Instructions
+    let instructions = "Use UP/DOWN arrows to select, ENTER to confirm";
+    let inst_font_size = px(20);
+    let inst_width = d.measure_text(instructions, inst_font_size);
+    let inst_x = (window_width - inst_width) / 2;
+    let inst_y = window_height - px(100);
+
+    d.draw_text(instructions, inst_x, inst_y, inst_font_size, Color::GRAY);
+}
+
+pub fn render_level_select(
+    d: &mut dyn Canvas,
+    window_width: i32,
+    window_height: i32,
+    selected_level: usize,
+) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    d.clear_background(Color::new(30, 30, 40, 255));
+
+    // Title
+    let title = "Select Level";
+    let title_font_size = px(60);
+    let title_width = d.measure_text(title, title_font_size);
+    let title_x = (window_width - title_width) / 2;
+    let title_y = window_height / 4;
+
+    d.draw_text(title, title_x, title_y, title_font_size, Color::WHITE);
+
+    // Level options
+    let levels = ["Level 1", "Level 2", "Level 3"];
+
+    let option_font_size = px(40);
+    let option_spacing = px(60);
+    let options_start_y = window_height / 2;
+
+    for (i, level) in levels.iter().enumerate() {
+        let option_width = d.measure_text(level, option_font_size);
+        let option_x = (window_width - option_width) / 2;
+        let option_y = options_start_y + (i as i32 * option_spacing);
+
+        let color = if i == selected_level {
+            Color::YELLOW
+        } else {
+            Color::LIGHTGRAY
+        };
+
+        d.draw_text(level, option_x, option_y, option_font_size, color);
+
+        // Draw selection indicator
+        if i == selected_level {
+            let arrow_x = option_x - px(40);
+            d.draw_text(">", arrow_x, option_y, option_font_size, Color::YELLOW);
+        }
+    }
+
+    // Instructions
+    let instructions = "Use UP/DOWN arrows to select, ENTER to confirm, ESC to go back";
+    let inst_font_size = px(20);
+    let inst_width = d.measure_text(instructions, inst_font_size);
+    let inst_x = (window_width - inst_width) / 2;
+    let inst_y = window_height - px(100);
+
+    d.draw_text(instructions, inst_x, inst_y, inst_font_size, Color::GRAY);
+}
+
+pub fn render_pause(
+    d: &mut dyn Canvas,
+    window_width: i32,
+    window_height: i32,
+    selected_option: usize,
+) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    // Dim the level underneath
+    d.draw_rectangle(0, 0, window_width, window_height, Color::new(0, 0, 0, 160));
+
+    let title = "Paused";
+    let title_font_size = px(60);
+    let title_width = d.measure_text(title, title_font_size);
+    let title_x = (window_width - title_width) / 2;
+    let title_y = window_height / 4;
+
+    d.draw_text(title, title_x, title_y, title_font_size, Color::WHITE);
+
+    let option_font_size = px(40);
+    let option_spacing = px(60);
+    let options_start_y = window_height / 2;
+
+    for (i, option) in pause_options().iter().enumerate() {
+        let option_width = d.measure_text(&option.text, option_font_size);
+        let option_x = (window_width - option_width) / 2;
+        let option_y = options_start_y + (i as i32 * option_spacing);
+
+        let color = if i == selected_option {
+            Color::YELLOW
+        } else {
+            Color::LIGHTGRAY
+        };
+
+        d.draw_text(&option.text, option_x, option_y, option_font_size, color);
+
+        // Draw selection indicator
+        if i == selected_option {
+            let arrow_x = option_x - px(40);
+            d.draw_text(">", arrow_x, option_y, option_font_size, Color::YELLOW);
+        }
+    }
+}
+
+pub fn render_game_over(
+    d: &mut dyn Canvas,
+    window_width: i32,
+    window_height: i32,
+    level_name: &str,
+) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    // Draw background
+    d.clear_background(Color::new(30, 30, 40, 255));
+
+    // Game Over message
+    let title = "Game Over";
+    let title_font_size = px(80);
+    let title_width = d.measure_text(title, title_font_size);
+    let title_x = (window_width - title_width) / 2;
+    let title_y = window_height / 4;
+
+    d.draw_text(title, title_x, title_y, title_font_size, Color::RED);
+
+    // Level failed message
+    let level_msg = format!("{} Failed", level_name);
+    let level_font_size = px(40);
+    let level_width = d.measure_text(&level_msg, level_font_size);
+    let level_x = (window_width - level_width) / 2;
+    let level_y = title_y + px(100);
+
+    d.draw_text(&level_msg, level_x, level_y, level_font_size, Color::WHITE);
+
+    // Try again message
+    let try_again = "Try Again!";
+    let try_font_size = px(30);
+    let try_width = d.measure_text(try_again, try_font_size);
+    let try_x = (window_width - try_width) / 2;
+    let try_y = window_height / 2;
+
+    d.draw_text(try_again, try_x, try_y, try_font_size, Color::LIGHTGRAY);
+
+    // Press enter instruction
+    let instruction = "Press ENTER to return to menu";
+    let inst_font_size = px(25);
+    let inst_width = d.measure_text(instruction, inst_font_size);
+    let inst_x = (window_width - inst_width) / 2;
+    let inst_y = window_height - px(150);
+
+    // Make it pulse
+    let time = d.time() as f32;
+    let alpha = ((time * 2.0).sin() * 0.5 + 0.5) * 255.0;
+    let inst_color = Color::new(255, 255, 255, alpha as u8);
+
+    d.draw_text(instruction, inst_x, inst_y, inst_font_size, inst_color);
+}
+
+pub fn render_victory(d: &mut dyn Canvas, window_width: i32, window_height: i32, level_name: &str) {
+    let px = |size| scaled(size, ui_scale(window_width, window_height));
+    // Draw background
+    d.clear_background(Color::new(30, 30, 40, 255));
+
+    // Victory message
+    let title = "Victory!";
+    let title_font_size = px(80);
+    let title_width = d.measure_text(title, title_font_size);
+    let title_x = (window_width - title_width) / 2;
+    let title_y = window_height / 4;
+
+    d.draw_text(title, title_x, title_y, title_font_size, Color::GOLD);
+
+    // Level completed message
+    let level_msg = format!("{} Completed!", level_name);
+    let level_font_size = px(40);
+    let level_width = d.measure_text(&level_msg, level_font_size);
+    let level_x = (window_width - level_width) / 2;
+    let level_y = title_y + px(100);
+
+    d.draw_text(&level_msg, level_x, level_y, level_font_size, Color::WHITE);
+
+    // Congratulations message
+    let congrats = "Congratulations!";
+    let congrats_font_size = px(30);
+    let congrats_width = d.measure_text(congrats, congrats_font_size);
+    let congrats_x = (window_width - congrats_width) / 2;
+    let congrats_y = window_height / 2;
+
+    d.draw_text(
+        congrats,
+        congrats_x,
+        congrats_y,
+        congrats_font_size,
+        Color::LIGHTGRAY,
+    );
+
+    // Press enter instruction
+    let instruction = "Press ENTER to return to menu";
+    let inst_font_size = px(25);
+    let inst_width = d.measure_text(instruction, inst_font_size);
+    let inst_x = (window_width - inst_width) / 2;
+    let inst_y = window_height - px(150);
+
+    // Make it pulse
+    let time = d.time() as f32;
+    let alpha = ((time * 2.0).sin() * 0.5 + 0.5) * 255.0;
+    let inst_color = Color::new(255, 255, 255, alpha as u8);
+
+    d.draw_text(instruction, inst_x, inst_y, inst_font_size, inst_color);
+}
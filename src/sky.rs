@@ -0,0 +1,65 @@
+use crate::assets::Assets;
+use crate::maze::grid_flag;
+use raylib::prelude::*;
+use std::f32::consts::PI;
+
+// Size the panorama is resampled to when loaded
+const SKY_WIDTH: usize = 1024;
+const SKY_HEIGHT: usize = 256;
+
+// Panoramic sky wrapped once around the player, shown over outdoor cells.
+// The image covers a full turn horizontally and runs from the top of the
+// screen down to the horizon.
+pub struct Sky {
+    pixels: Vec<Color>,
+    outdoor: Vec<Vec<bool>>,
+}
+
+impl Sky {
+    pub fn new(path: &str, outdoor: Vec<Vec<bool>>, assets: &Assets) -> Self {
+        // Indoor levels never show the sky, so don't bother loading it
+        let pixels = if outdoor.iter().flatten().any(|&open| open) {
+            Self::load_pixels(path, assets)
+        } else {
+            Vec::new()
+        };
+        Sky { pixels, outdoor }
+    }
+
+    fn load_pixels(path: &str, assets: &Assets) -> Vec<Color> {
+        let image = assets.image(path);
+        if image.is_placeholder() {
+            println!("No sky found at {} - outdoor cells keep the ceiling", path);
+            return Vec::new();
+        }
+        println!("Loaded sky: {}x{}", image.width, image.height);
+
+        // The sky is always opaque
+        image
+            .region(0, 0, image.width, image.height, SKY_WIDTH, SKY_HEIGHT)
+            .into_iter()
+            .map(|color| Color { a: 255, ..color })
+            .collect()
+    }
+
+    // Whether the level has any open sky to draw
+    pub fn is_visible(&self) -> bool {
+        !self.pixels.is_empty()
+    }
+
+    pub fn is_outdoor(&self, pos: Vector2, block_size: usize) -> bool {
+        grid_flag(&self.outdoor, pos, block_size)
+    }
+
+    // Sky colour looking along angle `a` (wraps every full turn), `height` going
+    // from 0.0 at the top of the screen to 1.0 at the horizon
+    pub fn sample(&self, a: f32, height: f32) -> Color {
+        let u = a.rem_euclid(2.0 * PI) / (2.0 * PI);
+        let x = ((u * SKY_WIDTH as f32) as usize).min(SKY_WIDTH - 1);
+        let y = ((height.clamp(0.0, 1.0) * SKY_HEIGHT as f32) as usize).min(SKY_HEIGHT - 1);
+        self.pixels
+            .get(y * SKY_WIDTH + x)
+            .copied()
+            .unwrap_or(Color::BLACK)
+    }
+}
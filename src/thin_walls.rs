@@ -0,0 +1,129 @@
+use crate::maze::Maze;
+use raylib::prelude::*;
+
+// A wall that is a single line through its cell instead of filling it. The
+// maze character shows where the line runs: '/' and '\' go corner to corner,
+// '[' and ']' hug the left and right edges, '^' and 'v' the top and bottom.
+#[derive(Clone, Copy)]
+pub struct ThinWall {
+    start: Vector2, // Cell units, (0, 0) is the top-left corner
+    end: Vector2,
+}
+
+impl ThinWall {
+    pub fn from_char(ch: char) -> Option<ThinWall> {
+        let ((x1, y1), (x2, y2)) = match ch {
+            '/' => ((0.0, 1.0), (1.0, 0.0)),
+            '\\' => ((0.0, 0.0), (1.0, 1.0)),
+            '[' => ((0.0, 0.0), (0.0, 1.0)),
+            ']' => ((1.0, 0.0), (1.0, 1.0)),
+            '^' => ((0.0, 0.0), (1.0, 0.0)),
+            'v' => ((0.0, 1.0), (1.0, 1.0)),
+            _ => return None,
+        };
+        Some(ThinWall {
+            start: Vector2::new(x1, y1),
+            end: Vector2::new(x2, y2),
+        })
+    }
+
+    // End points in world space for the wall in cell (i, j)
+    pub fn endpoints(&self, i: usize, j: usize, block_size: f32) -> (Vector2, Vector2) {
+        let corner = Vector2::new(i as f32 * block_size, j as f32 * block_size);
+        (
+            corner + self.start * block_size,
+            corner + self.end * block_size,
+        )
+    }
+
+    // Where a ray from `origin` along angle `a` hits the wall in cell (i, j):
+    // the distance along the ray and the texture column at that point
+    pub fn hit(
+        &self,
+        i: usize,
+        j: usize,
+        block_size: f32,
+        origin: Vector2,
+        a: f32,
+    ) -> Option<(f32, usize)> {
+        let (start, end) = self.endpoints(i, j, block_size);
+        let direction = Vector2::new(a.cos(), a.sin());
+        let (t, u) = intersect(origin, direction, start, end - start)?;
+        if t < 0.0 {
+            return None;
+        }
+
+        // The texture repeats every block along the wall, so diagonals aren't stretched
+        let along = u * (end - start).length() / block_size;
+        let tx = ((along * 128.0) as usize) % 128;
+        Some((t, tx))
+    }
+
+    // Whether the segment from `from` to `to` passes through the wall in cell (i, j)
+    fn crosses(&self, i: usize, j: usize, block_size: f32, from: Vector2, to: Vector2) -> bool {
+        let (start, end) = self.endpoints(i, j, block_size);
+        matches!(intersect(from, to - from, start, end - start), Some((t, _)) if (0.0..=1.0).contains(&t))
+    }
+
+    // Shortest distance from a point to the wall in cell (i, j)
+    fn distance_to(&self, i: usize, j: usize, block_size: f32, point: Vector2) -> f32 {
+        let (start, end) = self.endpoints(i, j, block_size);
+        let wall = end - start;
+        let u = ((point - start).dot(wall) / wall.dot(wall)).clamp(0.0, 1.0);
+        (start + wall * u - point).length()
+    }
+}
+
+// Solve origin + t * direction = start + u * wall, with u limited to the wall
+fn intersect(
+    origin: Vector2,
+    direction: Vector2,
+    start: Vector2,
+    wall: Vector2,
+) -> Option<(f32, f32)> {
+    let cross = |a: Vector2, b: Vector2| a.x * b.y - a.y * b.x;
+    let denominator = cross(direction, wall);
+    if denominator.abs() < 1e-6 {
+        return None; // Parallel to the wall
+    }
+    let offset = start - origin;
+    let t = cross(offset, wall) / denominator;
+    let u = cross(offset, direction) / denominator;
+    (0.0..=1.0).contains(&u).then_some((t, u))
+}
+
+// Thin wall in the maze cell under a world position, if any
+fn thin_wall_at(maze: &Maze, pos: Vector2, block_size: usize) -> Option<(usize, usize, ThinWall)> {
+    if pos.x < 0.0 || pos.y < 0.0 {
+        return None;
+    }
+    let i = pos.x as usize / block_size;
+    let j = pos.y as usize / block_size;
+    let wall = ThinWall::from_char(*maze.get(j)?.get(i)?)?;
+    Some((i, j, wall))
+}
+
+// Whether a square of half-size `margin` around (x, y) keeps clear of the
+// thin walls in the cells under its corners
+pub fn clear_of_thin_walls(x: f32, y: f32, maze: &Maze, block_size: usize, margin: f32) -> bool {
+    let center = Vector2::new(x, y);
+    [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)]
+        .iter()
+        .filter_map(|&(sx, sy)| {
+            thin_wall_at(
+                maze,
+                Vector2::new(x + sx * margin, y + sy * margin),
+                block_size,
+            )
+        })
+        .all(|(i, j, wall)| wall.distance_to(i, j, block_size as f32, center) >= margin)
+}
+
+// Whether a short move from `from` to `to` passes through a thin wall in the
+// cells at either end
+pub fn crosses_thin_wall(maze: &Maze, from: Vector2, to: Vector2, block_size: usize) -> bool {
+    [from, to].iter().any(|&pos| {
+        thin_wall_at(maze, pos, block_size)
+            .is_some_and(|(i, j, wall)| wall.crosses(i, j, block_size as f32, from, to))
+    })
+}
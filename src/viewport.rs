@@ -0,0 +1,78 @@
+use crate::canvas::{Canvas, Scaling, SoftwareCanvas};
+use crate::depth::DepthBuffer;
+use crate::framebuffer::Framebuffer;
+use raylib::prelude::*;
+
+// The buffers the 3D view and its sprites are drawn through, and the size
+// they are drawn at this frame
+pub struct View {
+    pub depth: DepthBuffer,
+    pub framebuffer: Framebuffer,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl View {
+    pub fn new(rays: usize) -> Self {
+        View {
+            depth: DepthBuffer::new(),
+            framebuffer: Framebuffer::new(rays),
+            width: 0,
+            height: 0,
+        }
+    }
+}
+
+// Everything the 3D view and its sprites are drawn with. The view can be
+// drawn at a lower resolution than the window and stretched to fill it,
+// which keeps big and fullscreen windows fast.
+pub struct Viewport {
+    pub view: View,
+    pub scale: f32, // Resolution of the view as a fraction of the window's
+    pub scaling: Scaling,
+    canvas: SoftwareCanvas, // The view at its own resolution, before stretching
+}
+
+impl Viewport {
+    pub fn new(rays: usize, scale: f32, scaling: Scaling) -> Self {
+        Viewport {
+            view: View::new(rays),
+            scale,
+            scaling,
+            canvas: SoftwareCanvas::new(0, 0),
+        }
+    }
+
+    // Resolution the view is drawn at in a window this big
+    pub fn size(&self, window_width: i32, window_height: i32) -> (i32, i32) {
+        let scale = self.scale.clamp(0.05, 1.0);
+        let scaled = |size: i32| ((size as f32 * scale).round() as i32).max(1);
+        (scaled(window_width), scaled(window_height))
+    }
+
+    // Run `draw` with a canvas for the view and the view sized to fit. At
+    // full resolution it draws straight to the window; otherwise the view
+    // is drawn in memory and then stretched over the window.
+    pub fn render<F>(&mut self, d: &mut dyn Canvas, window_width: i32, window_height: i32, draw: F)
+    where
+        F: FnOnce(&mut dyn Canvas, &mut View),
+    {
+        let (width, height) = self.size(window_width, window_height);
+        self.view.width = width;
+        self.view.height = height;
+        if (width, height) == (window_width, window_height) {
+            draw(d, &mut self.view);
+            return;
+        }
+
+        if self.canvas.width() != width || self.canvas.height() != height {
+            self.canvas = SoftwareCanvas::new(width, height);
+        }
+        // Sprites bob and pulse with the window's clock
+        self.canvas.set_time(d.time());
+        draw(&mut self.canvas, &mut self.view);
+
+        let dest = Rectangle::new(0.0, 0.0, window_width as f32, window_height as f32);
+        d.draw_pixels_scaled(dest, width as usize, self.canvas.pixels(), self.scaling);
+    }
+}
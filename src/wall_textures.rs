@@ -1,163 +1,296 @@
+use crate::animation::{Animation, Sequence};
+use crate::assets::Assets;
+use crate::enemy::EnemyType;
 use raylib::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+
+// Sprites are this many world units across unless their sheet says otherwise
+pub const DEFAULT_SPRITE_SIZE: f32 = 100.0;
+
+// Animation frames cut from a grid in a single image. The layout is read from
+// a `.sheet` file next to the image (see README); images without one are a
+// single static frame. Directional sheets split the frames into one equal
+// block per viewing angle.
+pub struct SpriteSheet {
+    frames: Vec<Vec<Color>>,
+    frame_size: usize,
+    angles: usize,
+    fps: f32,
+    sequences: Vec<(Sequence, usize, usize)>,
+    size: f32,
+    floor_aligned: bool,
+}
+
+// Everything a `.sheet` file can set
+struct SheetLayout {
+    columns: usize,
+    rows: usize,
+    angles: usize,
+    fps: f32,
+    sequences: Vec<(Sequence, usize, usize)>,
+    size: f32,
+    floor_aligned: bool,
+}
+
+impl SpriteSheet {
+    fn load(path: &str, texture_size: usize, assets: &Assets) -> SpriteSheet {
+        let image = assets.image(path);
+        let layout = Self::load_layout(path, assets);
+
+        let frame_width = image.width / layout.columns;
+        let frame_height = image.height / layout.rows;
+
+        // Keep small frames at their own resolution so large sheets stay light
+        let frame_size = frame_width.max(frame_height).clamp(1, texture_size);
+        let mut frames = Vec::with_capacity(layout.columns * layout.rows);
+        for row in 0..layout.rows {
+            for column in 0..layout.columns {
+                frames.push(image.region(
+                    column * frame_width,
+                    row * frame_height,
+                    frame_width,
+                    frame_height,
+                    frame_size,
+                    frame_size,
+                ));
+            }
+        }
+
+        // Every angle needs the same number of frames
+        let angles = if layout.angles > 1 && frames.len() % layout.angles == 0 {
+            layout.angles
+        } else {
+            1
+        };
+
+        SpriteSheet {
+            frames,
+            frame_size,
+            angles,
+            fps: layout.fps,
+            sequences: layout.sequences,
+            size: layout.size,
+            floor_aligned: layout.floor_aligned,
+        }
+    }
+
+    fn load_layout(path: &str, assets: &Assets) -> SheetLayout {
+        let mut layout = SheetLayout {
+            columns: 1,
+            rows: 1,
+            angles: 1,
+            fps: 0.0,
+            sequences: Vec::new(),
+            size: DEFAULT_SPRITE_SIZE,
+            floor_aligned: false,
+        };
+
+        let layout_path = Path::new(path).with_extension("sheet");
+        let Some(contents) = assets.read_to_string(&layout_path.to_string_lossy()) else {
+            return layout;
+        };
+
+        for line in contents.lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.as_slice() {
+                [] => {}
+                [comment, ..] if comment.starts_with('#') => {}
+                ["grid", c, r] => {
+                    layout.columns = c.parse().unwrap_or(1).max(1);
+                    layout.rows = r.parse().unwrap_or(1).max(1);
+                }
+                ["angles", a] => layout.angles = a.parse().unwrap_or(1).max(1),
+                ["fps", f] => layout.fps = f.parse().unwrap_or(0.0),
+                ["size", s] => {
+                    layout.size = s.parse().unwrap_or(DEFAULT_SPRITE_SIZE).max(1.0);
+                }
+                ["align", "floor"] => layout.floor_aligned = true,
+                ["align", "center"] => layout.floor_aligned = false,
+                [name, first, last] => match Sequence::from_name(name) {
+                    Some(sequence) => {
+                        let first: usize = first.parse().unwrap_or(0);
+                        let last: usize = last.parse().unwrap_or(first);
+                        layout.sequences.push((sequence, first, last.max(first)));
+                    }
+                    None => println!("Unknown sequence '{}' in {:?}", name, layout_path),
+                },
+                _ => println!("Skipping malformed line in {:?}: {}", layout_path, line),
+            }
+        }
+
+        layout
+    }
+
+    // Frame to draw for an entity's animation state seen from one of the
+    // sheet's angles. Missing sequences fall back to the first one in the
+    // sheet; "die" holds on its last frame.
+    pub fn frame_index(&self, animation: &Animation, angle: usize) -> usize {
+        let (first, last) = self
+            .sequences
+            .iter()
+            .find(|(sequence, _, _)| *sequence == animation.sequence)
+            .or(self.sequences.first())
+            .map(|&(_, first, last)| (first, last))
+            .unwrap_or((0, 0));
+
+        let count = last - first + 1;
+        let step = (animation.time * self.fps) as usize;
+        let frame = if animation.sequence == Sequence::Die {
+            step.min(count - 1)
+        } else {
+            step % count
+        };
+
+        let frames_per_angle = self.frames.len() / self.angles;
+        let block = (angle % self.angles) * frames_per_angle;
+        block + (first + frame).min(frames_per_angle - 1)
+    }
+
+    pub fn angles(&self) -> usize {
+        self.angles
+    }
+}
 
 pub struct WallTextures {
     wall_texture: Vec<Color>,
-    enemy_texture: Vec<Color>,
-    finish_texture: Vec<Color>,
+    sprites: HashMap<char, SpriteSheet>,
     texture_size: usize,
-    enabled: bool,
-    enemy_enabled: bool,
-    finish_enabled: bool,
 }
 
 impl WallTextures {
-    pub fn new() -> Self {
-        // Try to load wall texture
-        let (wall_texture, texture_size, enabled) =
-            if let Ok(image) = Image::load_image("assets/wall.png") {
-                println!("Loaded wall texture: {}x{}", image.width, image.height);
-                let target_size = (image.width as usize).min(256).max(128);
-                let data = Self::extract_colors(&image, target_size);
-                (data, target_size, true)
-            } else {
-                println!("No wall texture found at assets/wall.png - using solid colors");
-                (Vec::new(), 128, false)
-            };
-
-        // Try to load enemy texture
-        let (enemy_texture, enemy_enabled) =
-            if let Ok(image) = Image::load_image("assets/enemy.png") {
-                println!("Loaded enemy sprite: {}x{}", image.width, image.height);
-                let data = Self::extract_colors(&image, texture_size);
-                (data, true)
+    // Missing images are drawn as placeholders, except the finish, pickups
+    // and projectiles, which fall back to a plain colour
+    pub fn new(enemy_types: &[EnemyType], assets: &Assets) -> Self {
+        let wall = assets.image("assets/wall.png");
+        if wall.is_placeholder() {
+            println!("No wall texture found at assets/wall.png - using a placeholder");
+        } else {
+            println!("Loaded wall texture: {}x{}", wall.width, wall.height);
+        }
+        let texture_size = wall.width.clamp(128, 256);
+        let wall_texture = wall.region(0, 0, wall.width, wall.height, texture_size, texture_size);
+
+        let mut sprites = HashMap::new();
+
+        // One sprite sheet per enemy type
+        for enemy_type in enemy_types {
+            let sheet = SpriteSheet::load(&enemy_type.sprite, texture_size, assets);
+            if assets.image(&enemy_type.sprite).is_placeholder() {
+                println!(
+                    "No {} sprite found at {} - using a placeholder",
+                    enemy_type.name, enemy_type.sprite
+                );
             } else {
-                println!("No enemy sprite found at assets/enemy.png - enemies won't be visible");
-                (Vec::new(), false)
-            };
-
-        // Try to load finish texture
-        let (finish_texture, finish_enabled) =
-            if let Ok(image) = Image::load_image("assets/finish.png") {
-                println!("Loaded finish sprite: {}x{}", image.width, image.height);
-                let data = Self::extract_colors(&image, texture_size);
-                (data, true)
+                println!(
+                    "Loaded {} sprite sheet: {} frames",
+                    enemy_type.name,
+                    sheet.frames.len()
+                );
+            }
+            sprites.insert(enemy_type.key, sheet);
+        }
+
+        // Try to load finish, pickup and projectile sprite sheets
+        for (key, name, path) in [
+            ('w', "finish", "assets/finish.png"),
+            ('a', "ammo", "assets/ammo.png"),
+            ('*', "projectile", "assets/projectile.png"),
+        ] {
+            if assets.image(path).is_placeholder() {
+                println!(
+                    "No {} sprite found at {} - using fallback color",
+                    name, path
+                );
             } else {
-                println!("No finish sprite found at assets/finish.png - using fallback color");
-                (Vec::new(), false)
-            };
+                let sheet = SpriteSheet::load(path, texture_size, assets);
+                println!(
+                    "Loaded {} sprite sheet: {} frames",
+                    name,
+                    sheet.frames.len()
+                );
+                sprites.insert(key, sheet);
+            }
+        }
 
         WallTextures {
             wall_texture,
-            enemy_texture,
-            finish_texture,
+            sprites,
             texture_size,
-            enabled,
-            enemy_enabled,
-            finish_enabled,
         }
     }
 
-    fn extract_colors(image: &Image, target_size: usize) -> Vec<Color> {
-        let width = image.width as usize;
-        let height = image.height as usize;
-        let mut colors = Vec::with_capacity(target_size * target_size);
-
-        if width == target_size && height == target_size {
-            unsafe {
-                let data_ptr = image.data as *const u8;
-                if !data_ptr.is_null() {
-                    let data = std::slice::from_raw_parts(data_ptr, width * height * 4);
-                    for i in (0..data.len()).step_by(4) {
-                        colors.push(Color::new(data[i], data[i + 1], data[i + 2], data[i + 3]));
-                    }
-                }
-            }
+    #[inline(always)]
+    fn sample(texture: &[Color], size: usize, x: usize, y: usize, fallback: Color) -> Color {
+        let tx = (x * size / 128).min(size - 1);
+        let ty = (y * size / 128).min(size - 1);
+        let idx = ty * size + tx;
+
+        if idx < texture.len() {
+            texture[idx]
         } else {
-            for ty in 0..target_size {
-                for tx in 0..target_size {
-                    let fx = tx as f32 * width as f32 / target_size as f32;
-                    let fy = ty as f32 * height as f32 / target_size as f32;
-                    let sx = (fx as usize).min(width - 1);
-                    let sy = (fy as usize).min(height - 1);
-
-                    let color = unsafe {
-                        let data_ptr = image.data as *const u8;
-                        if !data_ptr.is_null() {
-                            let idx = (sy * width + sx) * 4;
-                            let data = std::slice::from_raw_parts(data_ptr, width * height * 4);
-                            if idx + 3 < data.len() {
-                                Color::new(data[idx], data[idx + 1], data[idx + 2], data[idx + 3])
-                            } else {
-                                Color::GRAY
-                            }
-                        } else {
-                            Color::GRAY
-                        }
-                    };
-                    colors.push(color);
-                }
-            }
+            fallback
+        }
+    }
+
+    fn fallback_color(sprite_type: char) -> Color {
+        match sprite_type {
+            'w' | 'a' => Color::GOLD,             // Finish and pickups
+            '*' => Color::new(255, 120, 40, 230), // Projectiles
+            _ => Color::RED,                      // Enemies
         }
-        colors
     }
 
     #[inline(always)]
     pub fn get_pixel(&self, x: usize, y: usize, sprite_type: char) -> Color {
-        if sprite_type == 'e' {
-            // Enemy sprite
-            if !self.enemy_enabled || self.enemy_texture.is_empty() {
-                return Color::RED; // Fallback color for enemies
-            }
-            let tx = (x * self.texture_size / 128).min(self.texture_size - 1);
-            let ty = (y * self.texture_size / 128).min(self.texture_size - 1);
-            let idx = ty * self.texture_size + tx;
-
-            if idx < self.enemy_texture.len() {
-                self.enemy_texture[idx]
-            } else {
-                Color::RED
-            }
-        } else if sprite_type == 'w' {
-            // Finish/win sprite
-            if !self.finish_enabled || self.finish_texture.is_empty() {
-                return Color::GOLD; // Fallback color for finish
-            }
-            let tx = (x * self.texture_size / 128).min(self.texture_size - 1);
-            let ty = (y * self.texture_size / 128).min(self.texture_size - 1);
-            let idx = ty * self.texture_size + tx;
-
-            if idx < self.finish_texture.len() {
-                self.finish_texture[idx]
-            } else {
-                Color::GOLD
-            }
+        if let Some(sheet) = self.sprites.get(&sprite_type) {
+            // First frame of a sprite
+            let fallback = Self::fallback_color(sprite_type);
+            Self::sample(&sheet.frames[0], sheet.frame_size, x, y, fallback)
+        } else if matches!(sprite_type, 'w' | 'a' | '*') {
+            Self::fallback_color(sprite_type)
         } else {
             // Wall texture
-            if !self.enabled || self.wall_texture.is_empty() {
-                return Color::GRAY;
-            }
-            let tx = (x * self.texture_size / 128).min(self.texture_size - 1);
-            let ty = (y * self.texture_size / 128).min(self.texture_size - 1);
-            let idx = ty * self.texture_size + tx;
+            Self::sample(&self.wall_texture, self.texture_size, x, y, Color::GRAY)
+        }
+    }
 
-            if idx < self.wall_texture.len() {
-                self.wall_texture[idx]
-            } else {
-                Color::GRAY
-            }
+    // Frame of a sprite matching an entity's animation state and the angle it
+    // is seen from (ignored for sheets without directional frames)
+    pub fn sprite_frame(&self, sprite_type: char, animation: &Animation, angle: usize) -> usize {
+        self.sprites
+            .get(&sprite_type)
+            .map_or(0, |sheet| sheet.frame_index(animation, angle))
+    }
+
+    #[inline(always)]
+    pub fn get_frame_pixel(&self, x: usize, y: usize, sprite_type: char, frame: usize) -> Color {
+        let fallback = Self::fallback_color(sprite_type);
+        match self.sprites.get(&sprite_type) {
+            Some(sheet) => Self::sample(&sheet.frames[frame], sheet.frame_size, x, y, fallback),
+            None => fallback,
         }
     }
 
-    pub fn is_enabled(&self) -> bool {
-        self.enabled
+    // Number of viewing angles in a sprite's sheet (1 when not directional)
+    pub fn sprite_angles(&self, sprite_type: char) -> usize {
+        self.sprites
+            .get(&sprite_type)
+            .map_or(1, |sheet| sheet.angles())
     }
 
-    pub fn is_enemy_enabled(&self) -> bool {
-        self.enemy_enabled
+    // World size of a sprite and whether it stands on the floor rather than
+    // floating centred at eye level
+    pub fn sprite_placement(&self, sprite_type: char) -> (f32, bool) {
+        self.sprites
+            .get(&sprite_type)
+            .map_or((DEFAULT_SPRITE_SIZE, false), |sheet| {
+                (sheet.size, sheet.floor_aligned)
+            })
     }
 
-    pub fn is_finish_enabled(&self) -> bool {
-        self.finish_enabled
+    pub fn has_sprite(&self, sprite_type: char) -> bool {
+        self.sprites.contains_key(&sprite_type)
     }
 }
 
@@ -0,0 +1,87 @@
+use crate::caster::cast_ray;
+use crate::enemy::Enemy;
+use crate::maze::Maze;
+use crate::player::Player;
+
+const FIRE_INTERVAL: f32 = 0.35; // Seconds between shots
+const FLASH_TIME: f32 = 0.08; // How long the muzzle flash stays on screen
+const HIT_RADIUS: f32 = 25.0; // Half width of an enemy for hit tests
+
+pub struct Weapon {
+    pub ammo: i32,
+    pub damage: i32,
+    pub cooldown: f32,
+    pub flash_timer: f32,
+}
+
+impl Weapon {
+    pub fn new(ammo: i32) -> Self {
+        Weapon {
+            ammo,
+            damage: 1,
+            cooldown: 0.0,
+            flash_timer: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.cooldown > 0.0 {
+            self.cooldown -= dt;
+        }
+        if self.flash_timer > 0.0 {
+            self.flash_timer -= dt;
+        }
+    }
+
+    pub fn can_fire(&self) -> bool {
+        self.ammo > 0 && self.cooldown <= 0.0
+    }
+
+    // Hitscan shot straight down the view direction. Returns the index of the
+    // enemy that was hit, if any.
+    pub fn fire(
+        &mut self,
+        player: &Player,
+        maze: &Maze,
+        enemies: &mut [Enemy],
+        block_size: usize,
+    ) -> Option<usize> {
+        if !self.can_fire() {
+            return None;
+        }
+
+        self.ammo -= 1;
+        self.cooldown = FIRE_INTERVAL;
+        self.flash_timer = FLASH_TIME;
+
+        // Anything behind the first wall along the ray is out of reach
+        let wall_distance = cast_ray(maze, player, player.a, block_size).distance;
+        let cos_a = player.a.cos();
+        let sin_a = player.a.sin();
+
+        // Ray vs sprite: closest living enemy whose billboard the ray passes through
+        let mut target: Option<(usize, f32)> = None;
+        for (i, enemy) in enemies.iter().enumerate() {
+            if !enemy.is_alive() {
+                continue;
+            }
+
+            let dx = enemy.pos.x - player.pos.x;
+            let dy = enemy.pos.y - player.pos.y;
+            let along = dx * cos_a + dy * sin_a;
+            let across = (dx * sin_a - dy * cos_a).abs();
+
+            if along <= 0.0 || along >= wall_distance || across > HIT_RADIUS {
+                continue;
+            }
+
+            if target.is_none_or(|(_, closest)| along < closest) {
+                target = Some((i, along));
+            }
+        }
+
+        let (i, _) = target?;
+        enemies[i].take_damage(self.damage);
+        Some(i)
+    }
+}
@@ -0,0 +1,248 @@
+use crate::animation::{Animation, Sequence};
+use crate::assets::Assets;
+use crate::checkpoint::Checkpoint;
+use crate::enemy::Enemy;
+use crate::fog::Fog;
+use crate::heights::HeightMap;
+use crate::lighting::{Light, LightMap};
+use crate::maze::{Level, Maze};
+use crate::panes::Panes;
+use crate::pickup::Pickup;
+use crate::player::{Controls, Player, process_events};
+use crate::portals::Portals;
+use crate::projectile::ProjectilePool;
+use crate::sky::Sky;
+use crate::weapon::Weapon;
+use raylib::prelude::*;
+
+pub const STARTING_AMMO: i32 = 12;
+pub const AMMO_PER_PICKUP: i32 = 6;
+pub const STARTING_LIVES: i32 = 2;
+
+// Things that happened during a step that the game reacts to with sounds or
+// by leaving the level
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    Shot,
+    CheckpointReached,
+    Damaged,
+    Died,
+    Won,
+}
+
+// Everything that changes while a level is played. It only needs a window
+// to draw, so tests and tools can step it on their own.
+pub struct World {
+    pub maze: Maze,
+    pub block_size: usize,
+    pub player: Player,
+    pub enemies: Vec<Enemy>,
+    pub finish_pos: Option<Vector2>,
+    pub finish_animation: Animation,
+    pub checkpoints: Vec<Checkpoint>,
+    pub projectiles: ProjectilePool,
+    pub ammo_pickups: Vec<Pickup>,
+    pub light_map: LightMap,
+    pub fog: Fog,
+    pub sky: Sky,
+    pub heights: HeightMap,
+    pub panes: Panes,
+    pub portals: Portals,
+    pub combat: bool,
+    pub weapon: Weapon,
+    pub lives: i32,
+    pub invulnerability_timer: f32,
+    pub checkpoint_timer: f32,
+    pub respawn_pos: Vector2, // Where the player comes back after losing a life
+    pub time: f32,
+}
+
+impl World {
+    // Images the level names, such as its sky, come from `assets`
+    pub fn new(level: Level, block_size: usize, assets: &Assets) -> Self {
+        // The finish glows on levels that use lighting
+        let mut lights = level.lights;
+        if !lights.is_empty()
+            && let Some(pos) = level.finish_pos
+        {
+            lights.push(Light::new(pos.x, pos.y, 300.0, Color::GOLD, false));
+        }
+        let light_map = LightMap::new(&level.maze, lights, level.ambient, block_size);
+
+        let mut panes = level.panes;
+        panes.load_textures(assets);
+
+        let player = Player::new(150.0, 150.0);
+        World {
+            maze: level.maze,
+            block_size,
+            // Until a checkpoint is reached the player respawns at the start
+            respawn_pos: player.pos,
+            player,
+            enemies: level.enemies,
+            finish_pos: level.finish_pos,
+            finish_animation: Animation::new(Sequence::Idle),
+            checkpoints: level.checkpoints,
+            projectiles: ProjectilePool::new(),
+            ammo_pickups: level.ammo_pickups,
+            light_map,
+            fog: level.fog,
+            sky: Sky::new(&level.sky, level.outdoor, assets),
+            heights: level.heights,
+            panes,
+            portals: level.portals,
+            combat: level.combat,
+            weapon: Weapon::new(STARTING_AMMO),
+            lives: STARTING_LIVES,
+            invulnerability_timer: 0.0,
+            checkpoint_timer: 0.0,
+            time: 0.0,
+        }
+    }
+
+    // Advance the level by one frame of `dt` seconds. Stops early once the
+    // player dies or reaches the finish.
+    pub fn step(&mut self, controls: &Controls, dt: f32) -> Vec<Event> {
+        let mut events = Vec::new();
+        self.time += dt;
+
+        process_events(
+            &mut self.player,
+            controls,
+            &self.maze,
+            &self.heights,
+            &self.portals,
+            self.block_size,
+            dt,
+        );
+
+        // Move enemies according to their behaviour
+        for enemy in self.enemies.iter_mut() {
+            enemy.update(
+                self.player.pos,
+                &self.maze,
+                &self.heights,
+                self.block_size,
+                &mut self.projectiles,
+                dt,
+            );
+        }
+        self.projectiles.update(&self.maze, self.block_size, dt);
+
+        // Advance the animations that aren't driven by enemy AI
+        self.finish_animation.update(dt);
+        for pickup in self.ammo_pickups.iter_mut() {
+            pickup.animation.update(dt);
+        }
+        self.light_map.update(self.time);
+
+        // Shooting and ammo pickups on combat levels
+        if self.combat {
+            self.weapon.update(dt);
+
+            if controls.fire && self.weapon.can_fire() {
+                events.push(Event::Shot);
+                self.weapon
+                    .fire(&self.player, &self.maze, &mut self.enemies, self.block_size);
+            }
+
+            let player_pos = self.player.pos;
+            let weapon = &mut self.weapon;
+            self.ammo_pickups.retain(|pickup| {
+                let dx = player_pos.x - pickup.pos.x;
+                let dy = player_pos.y - pickup.pos.y;
+                if (dx * dx + dy * dy).sqrt() < 30.0 {
+                    weapon.ammo += AMMO_PER_PICKUP;
+                    return false;
+                }
+                true
+            });
+        }
+
+        // Update invulnerability timer
+        if self.invulnerability_timer > 0.0 {
+            self.invulnerability_timer -= dt;
+        }
+        if self.checkpoint_timer > 0.0 {
+            self.checkpoint_timer -= dt;
+        }
+
+        // Activate any checkpoint the player walks through
+        for checkpoint in self.checkpoints.iter_mut() {
+            if checkpoint.activated {
+                continue;
+            }
+
+            let dx = self.player.pos.x - checkpoint.pos.x;
+            let dy = self.player.pos.y - checkpoint.pos.y;
+            let distance = (dx * dx + dy * dy).sqrt();
+
+            if distance < 30.0 {
+                checkpoint.activated = true;
+                self.respawn_pos = checkpoint.pos;
+                self.checkpoint_timer = 2.0;
+                events.push(Event::CheckpointReached);
+            }
+        }
+
+        // Check for enemy collisions if not invulnerable
+        if self.invulnerability_timer <= 0.0 {
+            let mut damage_taken = 0;
+
+            for enemy in &self.enemies {
+                if !enemy.is_alive() {
+                    continue;
+                }
+
+                let dx = self.player.pos.x - enemy.pos.x;
+                let dy = self.player.pos.y - enemy.pos.y;
+                let distance = (dx * dx + dy * dy).sqrt();
+
+                // If player touches an enemy (within 30 units)
+                if distance < 30.0 {
+                    damage_taken = enemy.damage;
+                    break; // Only take damage from one enemy at a time
+                }
+            }
+
+            // Enemy projectiles hurt just like touching an enemy
+            if damage_taken == 0 {
+                damage_taken = self.projectiles.check_hit(self.player.pos);
+            }
+
+            if damage_taken > 0 {
+                events.push(Event::Damaged);
+                self.lives -= damage_taken;
+
+                if self.lives <= 0 {
+                    events.push(Event::Died);
+                    return events;
+                }
+
+                // Respawn at the last checkpoint with enemies back at their posts
+                self.player.pos = self.respawn_pos;
+                for enemy in self.enemies.iter_mut() {
+                    enemy.reset();
+                }
+                self.projectiles.clear();
+
+                // Give temporary invulnerability after taking damage
+                self.invulnerability_timer = 2.0; // 2 seconds of invulnerability
+            }
+        }
+
+        // Check for win condition - if player is close to finish position
+        if let Some(finish) = self.finish_pos {
+            let dx = self.player.pos.x - finish.x;
+            let dy = self.player.pos.y - finish.y;
+            let distance = (dx * dx + dy * dy).sqrt();
+
+            // If player is within 30 units of the finish position, they win!
+            if distance < 30.0 {
+                events.push(Event::Won);
+            }
+        }
+
+        events
+    }
+}
@@ -0,0 +1,159 @@
+use raycaster::framebuffer::{DEFAULT_RAYS, Framebuffer};
+use raycaster::playing::render_world;
+use raycaster::{
+    Assets, Canvas, Scaling, SoftwareCanvas, Viewport, WallTextures, World, load_enemy_types,
+    load_maze, render_menu, scale_pixels,
+};
+use raylib::prelude::*;
+use std::rc::Rc;
+use std::{env, fs, process};
+
+const WIDTH: i32 = 320;
+const HEIGHT: i32 = 240;
+
+#[test]
+fn menus_render_without_a_window() {
+    let mut canvas = SoftwareCanvas::new(WIDTH, HEIGHT);
+    render_menu(&mut canvas, WIDTH, HEIGHT, 0);
+
+    // Background in the corner, title text somewhere across the top quarter
+    assert_eq!(canvas.pixel(0, 0), Some(Color::new(30, 30, 40, 255)));
+    let title_row = HEIGHT / 4 + 5;
+    assert!((0..WIDTH).any(|x| canvas.pixel(x, title_row) == Some(Color::WHITE)));
+}
+
+#[test]
+fn a_level_frame_renders_without_a_window() {
+    let enemy_types = load_enemy_types("enemies.txt").unwrap();
+    let assets = Assets::new();
+    let world = World::new(load_maze("level2.txt", &enemy_types).unwrap(), 100, &assets);
+    let wall_textures = WallTextures::new(&enemy_types, &assets);
+    let mut viewport = Viewport::new(DEFAULT_RAYS, 1.0, Scaling::Pixel);
+
+    let mut canvas = SoftwareCanvas::new(WIDTH, HEIGHT);
+    render_world(
+        &mut canvas,
+        &world,
+        &wall_textures,
+        &mut viewport,
+        WIDTH,
+        HEIGHT,
+    );
+
+    // The view is drawn right across the screen, with the minimap on top of it
+    assert_eq!(viewport.view.depth.width(), WIDTH as usize);
+    let distinct = |row: i32| {
+        let mut colors: Vec<Color> = (0..WIDTH).filter_map(|x| canvas.pixel(x, row)).collect();
+        colors.dedup();
+        colors.len()
+    };
+    assert!(distinct(HEIGHT / 2) > 1);
+    assert_ne!(
+        canvas.pixel(WIDTH / 2, 5),
+        canvas.pixel(WIDTH / 2, HEIGHT - 5)
+    );
+}
+
+#[test]
+fn rays_split_the_view_into_whole_columns() {
+    // More rays than columns are cut back to one per column
+    let mut framebuffer = Framebuffer::new(1000);
+    assert_eq!(framebuffer.ray_count(WIDTH), WIDTH as usize);
+
+    // Otherwise each ray gets a run of columns, meeting the next without gaps
+    framebuffer.set_rays(7);
+    let columns = framebuffer.paint(20, 4, |i, strip| {
+        strip.fill(0..4, Color::new(i as u8, 0, 0, 255));
+        strip.columns()
+    });
+    assert_eq!(columns.len(), 7);
+    assert_eq!(columns[0].start, 0);
+    assert_eq!(columns[6].end, 20);
+    assert!(columns.windows(2).all(|pair| pair[0].end == pair[1].start));
+
+    let mut canvas = SoftwareCanvas::new(20, 4);
+    framebuffer.draw(&mut canvas);
+    for (i, ray_columns) in columns.iter().enumerate() {
+        for x in ray_columns.clone() {
+            assert_eq!(canvas.pixel(x, 3), Some(Color::new(i as u8, 0, 0, 255)));
+        }
+    }
+}
+
+#[test]
+fn canvas_blends_translucent_shapes() {
+    let mut canvas = SoftwareCanvas::new(10, 10);
+    canvas.clear_background(Color::BLACK);
+    canvas.draw_rectangle(0, 0, 5, 10, Color::new(255, 255, 255, 255));
+    canvas.draw_rectangle(5, 0, 5, 10, Color::new(255, 255, 255, 128));
+
+    assert_eq!(canvas.pixel(2, 2), Some(Color::WHITE));
+    let half = canvas.pixel(7, 2).unwrap();
+    assert!(half.r > 100 && half.r < 155);
+    assert_eq!(canvas.pixel(10, 2), None);
+}
+
+#[test]
+fn a_scaled_view_is_stretched_over_the_window() {
+    let enemy_types = load_enemy_types("enemies.txt").unwrap();
+    let assets = Assets::new();
+    let world = World::new(load_maze("level2.txt", &enemy_types).unwrap(), 100, &assets);
+    let wall_textures = WallTextures::new(&enemy_types, &assets);
+    let mut viewport = Viewport::new(DEFAULT_RAYS, 0.5, Scaling::Pixel);
+
+    let mut canvas = SoftwareCanvas::new(WIDTH, HEIGHT);
+    render_world(
+        &mut canvas,
+        &world,
+        &wall_textures,
+        &mut viewport,
+        WIDTH,
+        HEIGHT,
+    );
+
+    // Half the columns are cast, but the view still reaches the far edge
+    assert_eq!(viewport.size(WIDTH, HEIGHT), (WIDTH / 2, HEIGHT / 2));
+    assert_eq!(viewport.view.depth.width(), (WIDTH / 2) as usize);
+    assert_ne!(canvas.pixel(WIDTH - 1, HEIGHT / 2), Some(Color::BLACK));
+
+    // Pixel scaling repeats each pixel, smooth scaling blends between them
+    let pixels = [Color::BLACK, Color::WHITE];
+    let sharp = scale_pixels(2, &pixels, 4, 1, Scaling::Pixel);
+    assert_eq!(
+        sharp,
+        [Color::BLACK, Color::BLACK, Color::WHITE, Color::WHITE]
+    );
+    let smooth = scale_pixels(2, &pixels, 4, 1, Scaling::Smooth);
+    assert!(smooth[1].r > 0 && smooth[1].r < 255);
+}
+
+#[test]
+fn assets_are_searched_for_cached_and_stood_in_for() {
+    // The game's own files are found, and override directories come first
+    let mut assets = Assets::new();
+    assert!(assets.resolve("level1.txt").is_some());
+    // Unique per process so parallel runs don't share the directory
+    let dir = env::temp_dir().join(format!(
+        "raycaster_{}_assets_are_searched_for_cached_and_stood_in_for",
+        process::id()
+    ));
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("level1.txt"), "wwww\n").unwrap();
+    assets.add_override(&dir);
+    let resolved = assets.resolve("level1.txt");
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(resolved, Some(dir.join("level1.txt")));
+
+    // Missing images become a placeholder, decoded once and shared
+    let missing = assets.image("assets/no_such_image.png");
+    assert!(missing.is_placeholder());
+    assert_eq!(missing.pixels.len(), missing.width * missing.height);
+    assert!(Rc::ptr_eq(
+        &missing,
+        &assets.image("assets/no_such_image.png")
+    ));
+
+    // Missing sounds are silence, and missing music isn't played at all
+    assert_eq!(assets.sound("assets/no_such_sound.mp3").count(), 0);
+    assert!(assets.music("assets/no_such_music.mp3").is_none());
+}
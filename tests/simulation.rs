@@ -0,0 +1,306 @@
+use raycaster::enemy::Behaviour;
+use raycaster::generator::generate_maze;
+use raycaster::maze::has_line_of_sight;
+use raycaster::player::is_valid_position;
+use raycaster::replay::{EndState, Outcome};
+use raycaster::{
+    Assets, Controls, Enemy, EnemyType, LevelSource, Replay, World, cast_ray, load_enemy_types,
+    load_maze, parse_maze,
+};
+use raylib::prelude::Vector2;
+use std::{env, fs, process};
+
+const BLOCK_SIZE: usize = 100;
+const FRAME: f32 = 1.0 / 60.0;
+const COLLISION_MARGIN: f32 = 10.0; // The player's, from player.rs
+
+fn world(level: &str) -> World {
+    let enemy_types = load_enemy_types("enemies.txt").unwrap();
+    World::new(
+        load_maze(level, &enemy_types).unwrap(),
+        BLOCK_SIZE,
+        &Assets::new(),
+    )
+}
+
+#[test]
+fn levels_load_without_a_window() {
+    for level in ["level1.txt", "level2.txt", "level3.txt"] {
+        let world = world(level);
+        assert!(!world.maze.is_empty(), "{} has no maze", level);
+        assert!(world.finish_pos.is_some(), "{} has no finish", level);
+    }
+}
+
+// A walled room whose east wall's face is at x = 700, with the player
+// starting at (150, 150) looking straight at it
+fn room() -> World {
+    let room = "+------+\n|      |\n|      |\n+------+\n";
+    let mut world = World::new(parse_maze("room", room, &[]), BLOCK_SIZE, &Assets::new());
+    world.player.a = 0.0;
+    world
+}
+
+#[test]
+fn rays_stop_at_walls() {
+    let world = room();
+    let hit = cast_ray(&world.maze, &world.player, world.player.a, BLOCK_SIZE);
+    assert!((hit.distance - 550.0).abs() < 0.5);
+}
+
+#[test]
+fn walking_forward_moves_the_player_until_a_wall() {
+    let mut world = room();
+    let controls = Controls {
+        forward: true,
+        ..Controls::default()
+    };
+
+    for _ in 0..120 {
+        world.step(&controls, FRAME);
+        let pos = world.player.pos;
+        assert!(is_valid_position(
+            pos.x,
+            pos.y,
+            &world.maze,
+            BLOCK_SIZE,
+            COLLISION_MARGIN
+        ));
+    }
+
+    // Two seconds is far enough to reach the wall, so the player ends up
+    // stopped with their edge no more than a margin short of it
+    let gap = 700.0 - (world.player.pos.x + COLLISION_MARGIN);
+    assert!((0.0..=COLLISION_MARGIN).contains(&gap));
+}
+
+#[test]
+fn standing_still_keeps_the_player_in_place() {
+    // Without enemies nothing can knock the player back to a checkpoint
+    let mut world = world("level2.txt");
+    world.enemies.clear();
+    let start = world.player.pos;
+    for _ in 0..60 {
+        world.step(&Controls::default(), FRAME);
+    }
+    assert_eq!(world.player.pos, start);
+    assert!((world.time - 1.0).abs() < 0.01);
+}
+
+#[test]
+fn jumping_leaves_the_ground_and_lands() {
+    let mut world = world("level1.txt");
+    let jump = Controls {
+        jump: true,
+        ..Controls::default()
+    };
+    world.step(&jump, FRAME);
+    assert!(world.player.z > 0.0);
+
+    for _ in 0..120 {
+        world.step(&Controls::default(), FRAME);
+    }
+    assert_eq!(world.player.z, 0.0);
+}
+
+#[test]
+fn generated_mazes_depend_only_on_the_seed() {
+    assert_eq!(generate_maze(8, 6, 42), generate_maze(8, 6, 42));
+    assert_ne!(generate_maze(8, 6, 42), generate_maze(8, 6, 43));
+
+    let source = LevelSource::Generated {
+        cols: 8,
+        rows: 6,
+        seed: 42,
+    };
+    assert_eq!(source.to_string().parse(), Ok(source.clone()));
+    let assets = Assets::new();
+    let level = source
+        .load(&load_enemy_types("enemies.txt").unwrap(), &assets)
+        .unwrap();
+    let world = World::new(level, BLOCK_SIZE, &assets);
+    assert_eq!(world.maze.len(), 13);
+    assert_eq!(world.maze[0].len(), 25);
+    assert!(world.finish_pos.is_some());
+    assert!(is_valid_position(
+        world.player.pos.x,
+        world.player.pos.y,
+        &world.maze,
+        BLOCK_SIZE,
+        10.0
+    ));
+}
+
+#[test]
+fn replays_end_where_they_were_recorded() {
+    // Walk forward while turning, then stand still
+    let source = LevelSource::File("level1.txt".to_string());
+    let (enemy_types, assets) = (load_enemy_types("enemies.txt").unwrap(), Assets::new());
+    let mut world = World::new(
+        source.load(&enemy_types, &assets).unwrap(),
+        BLOCK_SIZE,
+        &assets,
+    );
+    let mut replay = Replay::new(source);
+    for frame in 0..90 {
+        let controls = Controls {
+            forward: frame < 60,
+            turn: 0.01,
+            ..Controls::default()
+        };
+        replay.record(FRAME, &controls);
+        world.step(&controls, FRAME);
+    }
+    replay.end = Some(Outcome::of(&world, EndState::Playing));
+
+    // Unique per process so parallel runs don't overwrite each other's file
+    let path = env::temp_dir().join(format!(
+        "raycaster_{}_replays_end_where_they_were_recorded.txt",
+        process::id()
+    ));
+    let path = path.to_str().unwrap();
+    replay.save(path).unwrap();
+    let loaded = Replay::load(path);
+    fs::remove_file(path).unwrap();
+    let loaded = loaded.unwrap();
+    assert_eq!(loaded.frames.len(), 90);
+    assert!(loaded.verify(&enemy_types, &assets, BLOCK_SIZE).is_ok());
+
+    // Somewhere else entirely is caught
+    let mut moved = loaded;
+    moved.end = moved.end.map(|end| Outcome {
+        x: end.x + 50.0,
+        ..end
+    });
+    assert!(moved.verify(&enemy_types, &assets, BLOCK_SIZE).is_err());
+}
+
+#[test]
+fn missing_files_are_errors_naming_the_file() {
+    let level = load_maze("no_such_level.txt", &[]).err().unwrap();
+    assert!(level.contains("no_such_level.txt"));
+    let enemies = load_enemy_types("no_such_enemies.txt").err().unwrap();
+    assert!(enemies.contains("no_such_enemies.txt"));
+
+    let source = LevelSource::File("no_such_level.txt".to_string());
+    assert!(source.load(&[], &Assets::new()).is_err());
+
+    let replay = Replay::new(source);
+    let saved = replay.save("no_such_dir/replay.txt").err().unwrap();
+    assert!(saved.contains("no_such_dir/replay.txt"));
+}
+
+#[test]
+fn walking_speed_does_not_depend_on_the_frame_rate() {
+    let forward = Controls {
+        forward: true,
+        ..Controls::default()
+    };
+    let walk = |room: &str, frames: usize, dt: f32| {
+        let mut world = World::new(parse_maze("room", room, &[]), BLOCK_SIZE, &Assets::new());
+        world.player.a = 0.0;
+        for _ in 0..frames {
+            world.step(&forward, dt);
+        }
+        world.player.pos.x
+    };
+
+    // A sixth of a second at 600 units a second, however it is sliced
+    let room = "+------+\n|      |\n|      |\n+------+\n";
+    for fps in [60, 30, 12] {
+        assert!((walk(room, fps / 6, 1.0 / fps as f32) - 250.0).abs() < 0.5);
+    }
+
+    // Half a second in one frame would land past a one-cell wall, but the
+    // move is taken in short steps so the wall's face at x = 300 still stops
+    // the player
+    let room = "+------+\n|  |   |\n|  |   |\n+------+\n";
+    let x = walk(room, 4, 0.5);
+    assert!((280.0..300.0).contains(&x));
+}
+
+#[test]
+fn chasing_enemies_climb_steps_but_not_ledges() {
+    let chaser = EnemyType {
+        key: 'e',
+        name: "chaser".to_string(),
+        sprite: String::new(),
+        speed: 200.0,
+        damage: 1,
+        health: 1,
+        detection_radius: 1000.0,
+        behaviour: Behaviour::Chase,
+    };
+    // A raised strip of floor across a walled room, between the enemy and the player
+    let chase = |height: f32| {
+        let room = format!(
+            "@floor R {}\n+------+\n|  R   |\n|  R   |\n+------+\n",
+            height
+        );
+        let mut world = World::new(parse_maze("room", &room, &[]), BLOCK_SIZE, &Assets::new());
+        world.player.pos.x = 650.0;
+        world.player.pos.y = 150.0;
+        world.enemies = vec![Enemy::new(150.0, 150.0, &chaser)];
+        for _ in 0..90 {
+            world.step(&Controls::default(), FRAME);
+        }
+        world.enemies[0].pos.x
+    };
+
+    assert!(chase(20.0) > 400.0);
+    assert!(chase(60.0) < 300.0);
+}
+
+#[test]
+fn enemy_types_cannot_take_level_characters() {
+    let path = env::temp_dir().join(format!(
+        "raycaster_{}_enemy_types_cannot_take_level_characters.txt",
+        process::id()
+    ));
+    let path = path.to_str().unwrap();
+    fs::write(
+        path,
+        "e wanderer assets/enemy.png 60 1 2 250 wander\n\
+         w winner assets/enemy.png 60 1 2 250 wander\n\
+         * spark assets/enemy.png 60 1 2 250 chase\n\
+         / slash assets/enemy.png 60 1 2 250 chase\n\
+         + corner assets/enemy.png 60 1 2 250 chase\n\
+         | pillar assets/enemy.png 60 1 2 250 chase\n\
+         gg ghoul assets/enemy.png 60 1 2 250 ghost\n",
+    )
+    .unwrap();
+    let enemy_types = load_enemy_types(path);
+    fs::remove_file(path).unwrap();
+
+    let keys: Vec<char> = enemy_types.unwrap().iter().map(|t| t.key).collect();
+    assert_eq!(keys, ['e']);
+}
+
+#[test]
+fn portals_with_bad_coordinates_are_skipped() {
+    let room = "@portal 1 1 4 x\n@portal 1 1 4 1 half\n@portal 1 1 4 1 1\n+----+\n|    |\n+----+\n";
+    let level = parse_maze("room", room, &[]);
+    let pairs: Vec<_> = level.portals.pairs().collect();
+    assert_eq!(pairs, [((1, 1), (4, 1))]);
+}
+
+#[test]
+fn lights_with_bad_coordinates_are_skipped() {
+    let room = "@light one 1 300 255 255 255\n@light 2 1 300 255 255 255\n+----+\n|    |\n+----+\n";
+    let level = parse_maze("room", room, &[]);
+    assert_eq!(level.lights.len(), 1);
+    assert_eq!(level.lights[0].pos.x, 250.0);
+}
+
+#[test]
+fn levels_with_short_rows_load_and_light() {
+    // The middle row stops short of the others
+    let level = "@light 1 1 300 255 255 255\n+-----+\n|  \n+-----+\n";
+    let world = World::new(parse_maze("short", level, &[]), BLOCK_SIZE, &Assets::new());
+    assert!(!has_line_of_sight(
+        &world.maze,
+        Vector2::new(150.0, 150.0),
+        Vector2::new(550.0, 150.0),
+        BLOCK_SIZE
+    ));
+}